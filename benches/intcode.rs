@@ -0,0 +1,81 @@
+//! Timings for IntCode heavy workloads. Run with `cargo bench --bench intcode`.
+
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use advent_of_code_2019::{day23, intcode::IntCode, parse};
+
+fn load(day: &str) -> Vec<isize> {
+    let path = Path::new("data").join(format!("{}.txt", day));
+    parse::intcode(BufReader::new(File::open(path).unwrap())).unwrap()
+}
+
+fn bench(name: &str, iterations: u32, mut f: impl FnMut()) {
+    let mut times: Vec<Duration> = (0..iterations)
+        .map(|_| {
+            let start = Instant::now();
+            f();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    let mean = times.iter().sum::<Duration>() / iterations;
+    println!(
+        "{:<24} mean {:>10.3?}  min {:>10.3?}  ({} iterations)",
+        name, mean, times[0], iterations
+    );
+}
+
+fn main() {
+    // Day 9 part 2: a single long-running VM with heavy use of relative mode. The stepped run
+    // decodes every instruction as it goes; `run` uses the pre-decoded instruction cache.
+    let boost = load("day09");
+    bench("day09 boost sensor step", 10, || {
+        let mut computer = IntCode::new(boost.clone(), vec![2]);
+        computer.run_traced(0, &mut ()).unwrap();
+        assert_eq!(computer.take_output().len(), 1);
+    });
+    bench("day09 boost sensor", 10, || {
+        let mut computer = IntCode::new(boost.clone(), vec![2]);
+        computer.run(0).unwrap();
+        assert_eq!(computer.take_output().len(), 1);
+    });
+
+    // Day 19 part 1: thousands of short-lived VMs, one per point. The cached run forks a
+    // single pre-decoded VM for every point.
+    let drone = load("day19");
+    let scan = |f: &dyn Fn(isize, isize) -> isize| {
+        let count = (0..50)
+            .flat_map(|y| (0..50).map(move |x| (x, y)))
+            .filter(|&(x, y)| f(x, y) == 1)
+            .count();
+        assert!(count > 0);
+    };
+    bench("day19 tractor beam step", 10, || {
+        scan(&|x, y| {
+            let mut computer = IntCode::new(drone.clone(), vec![x, y]);
+            computer.run_traced(1, &mut ()).unwrap();
+            computer.take_output()[0]
+        })
+    });
+    let mut template = IntCode::new(drone.clone(), vec![]);
+    template.predecode();
+    bench("day19 tractor beam", 10, || {
+        scan(&|x, y| {
+            let mut computer = template.fork();
+            computer.set_input(vec![x, y]);
+            computer.run(1).unwrap();
+            computer.take_output()[0]
+        })
+    });
+
+    // Day 23 part 2: 50 networked VMs exchanging packets.
+    let network = fs::read("data/day23.txt").unwrap();
+    bench("day23 network", 10, || {
+        day23::star_two(BufReader::new(&network[..])).unwrap();
+    });
+}
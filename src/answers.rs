@@ -0,0 +1,303 @@
+//! Expected answers, read from a small subset of TOML such as `data/answers.toml`:
+//!
+//! ```toml
+//! # Comments start with a hash.
+//! [day01]
+//! part1 = 3512133
+//! part2 = 5265294
+//!
+//! [day08]
+//! part2 = "CFLUL"
+//!
+//! [day11]
+//! part2 = """
+//! .####..##.
+//! .#....#..#"""
+//! ```
+//!
+//! Each `[dayNN]` table holds `part1` and `part2`, either of which may be left out. Values are
+//! integers, `"basic strings"` or `"""multi-line strings"""`; as in TOML, a newline straight
+//! after the opening `"""` is not part of the string.
+
+use std::{collections::BTreeMap, error::Error, fmt::Display, str::FromStr};
+
+use crate::solution::Answer;
+
+/// Expected answers by day and part.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Answers {
+    answers: BTreeMap<(usize, usize), String>,
+}
+
+/// How a computed answer compares with the expected one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    Pass,
+    Mismatch {
+        expected: String,
+        actual: String,
+    },
+    /// There is no expected answer for this day and part.
+    Unknown,
+}
+
+impl Answers {
+    /// The expected answer for one part of a day, as text.
+    pub fn get(&self, day: usize, part: usize) -> Option<&str> {
+        self.answers.get(&(day, part)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, day: usize, part: usize, answer: String) {
+        self.answers.insert((day, part), answer);
+    }
+
+    pub fn len(&self) -> usize {
+        self.answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.answers.is_empty()
+    }
+
+    pub fn check(&self, day: usize, part: usize, answer: &Answer) -> Check {
+        match self.get(day, part) {
+            None => Check::Unknown,
+            Some(expected) if *expected == answer.to_string() => Check::Pass,
+            Some(expected) => Check::Mismatch {
+                expected: expected.to_string(),
+                actual: answer.to_string(),
+            },
+        }
+    }
+}
+
+/// A line-by-line diff of two answers: unchanged lines are prefixed with two spaces, expected
+/// lines with `- ` and actual lines with `+ `.
+pub fn diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut lines = Vec::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => lines.push(format!("  {}", e)),
+            (e, a) => {
+                lines.extend(e.map(|e| format!("- {}", e)));
+                lines.extend(a.map(|a| format!("+ {}", a)));
+            }
+        }
+    }
+    lines.join("\n")
+}
+
+/// A syntax error in an answers file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnswersError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AnswersError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AnswersError {}
+
+/// Checks that nothing but whitespace or a comment is left on the line.
+fn end_of_line(rest: &str) -> Result<(), String> {
+    match rest.trim_start() {
+        "" => Ok(()),
+        rest if rest.starts_with('#') => Ok(()),
+        rest => Err(format!("unexpected {:?}", rest)),
+    }
+}
+
+/// Replaces the escapes TOML allows in basic strings.
+fn unescape(text: &str) -> Result<String, String> {
+    let mut unescaped = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            other => return Err(format!("unsupported escape {:?}", other)),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Parses a `"basic string"`, returning its contents and the rest of the line.
+fn basic_string(value: &str) -> Result<(String, &str), String> {
+    let mut escaped = false;
+    for (i, c) in value.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Ok((unescape(&value[1..i])?, &value[i + 1..])),
+            _ => {}
+        }
+    }
+    Err("unterminated string".to_string())
+}
+
+fn integer(value: &str) -> Result<String, String> {
+    let value = value.split('#').next().unwrap().trim();
+    let digits = value.replace('_', "");
+    match digits.parse::<i128>() {
+        Ok(n) => Ok(n.to_string()),
+        Err(_) => Err(format!("bad value {:?}", value)),
+    }
+}
+
+fn parse_table(line: &str) -> Result<usize, String> {
+    let (name, rest) = line[1..].split_once(']').ok_or("unterminated table")?;
+    end_of_line(rest)?;
+    name.trim()
+        .strip_prefix("day")
+        .and_then(|day| day.parse().ok())
+        .ok_or_else(|| format!("expected a [dayNN] table, found {:?}", name))
+}
+
+fn parse_part(key: &str) -> Result<usize, String> {
+    match key.trim() {
+        "part1" => Ok(1),
+        "part2" => Ok(2),
+        key => Err(format!("unknown key {:?}", key)),
+    }
+}
+
+impl FromStr for Answers {
+    type Err = AnswersError;
+
+    fn from_str(text: &str) -> Result<Answers, AnswersError> {
+        let mut answers = Answers::default();
+        let mut day = None;
+        let mut lines = text.lines().enumerate();
+        while let Some((i, line)) = lines.next() {
+            let error = |message| AnswersError {
+                line: i + 1,
+                message,
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with('[') {
+                day = Some(parse_table(line).map_err(error)?);
+                continue;
+            }
+            let day = day.ok_or_else(|| error("key outside of a [dayNN] table".to_string()))?;
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| error("expected key = value".to_string()))?;
+            let part = parse_part(key).map_err(error)?;
+            let value = value.trim();
+            let answer = if let Some(first) = value.strip_prefix("\"\"\"") {
+                let mut raw = first.to_string();
+                let mut closing = raw.find("\"\"\"");
+                // A newline straight after the opening quotes is dropped.
+                let mut newline = !first.is_empty();
+                while closing.is_none() {
+                    let (_, next) = lines
+                        .next()
+                        .ok_or_else(|| error("unterminated multi-line string".to_string()))?;
+                    if newline {
+                        raw.push('\n');
+                    }
+                    newline = true;
+                    raw.push_str(next);
+                    closing = raw.find("\"\"\"");
+                }
+                let closing = closing.unwrap();
+                end_of_line(&raw[closing + 3..]).map_err(error)?;
+                unescape(&raw[..closing]).map_err(error)?
+            } else if value.starts_with('"') {
+                let (answer, rest) = basic_string(value).map_err(error)?;
+                end_of_line(rest).map_err(error)?;
+                answer
+            } else {
+                integer(value).map_err(error)?
+            };
+            if answers.get(day, part).is_some() {
+                return Err(error(format!("day {} part {} is given twice", day, part)));
+            }
+            answers.insert(day, part, answer);
+        }
+        Ok(answers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"
+# Sample answers
+[day01]
+part1 = 3_512_133 # with separators
+part2 = 5265294
+
+[day8]
+part2 = "C\"F\\L # not a comment"
+
+[day11]
+part2 = """
+.##.
+#..#"""
+"#;
+
+    #[test]
+    fn test_parse() {
+        let answers: Answers = SAMPLE.parse().unwrap();
+        assert_eq!(answers.len(), 4);
+        assert_eq!(answers.get(1, 1), Some("3512133"));
+        assert_eq!(answers.get(1, 2), Some("5265294"));
+        assert_eq!(answers.get(8, 2), Some("C\"F\\L # not a comment"));
+        assert_eq!(answers.get(11, 2), Some(".##.\n#..#"));
+        assert_eq!(answers.get(11, 1), None);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |text: &str| text.parse::<Answers>().unwrap_err().to_string();
+        assert_eq!(error("part1 = 1"), "line 1: key outside of a [dayNN] table");
+        assert_eq!(error("[day1]\npart3 = 1"), "line 2: unknown key \"part3\"");
+        assert_eq!(error("[day1]\npart1 = one"), "line 2: bad value \"one\"");
+        assert_eq!(
+            error("[day1]\npart1 = 1\npart1 = 2"),
+            "line 3: day 1 part 1 is given twice"
+        );
+        assert_eq!(
+            error("[day1]\npart2 = \"\"\"\nabc"),
+            "line 2: unterminated multi-line string"
+        );
+        assert_eq!(
+            error("[puzzle]"),
+            "line 1: expected a [dayNN] table, found \"puzzle\""
+        );
+    }
+
+    #[test]
+    fn test_check_and_diff() {
+        let mut answers = Answers::default();
+        answers.insert(1, 1, "42".to_string());
+        answers.insert(11, 2, "ab\ncd\nef".to_string());
+        assert_eq!(answers.check(1, 1, &Answer::Number(42)), Check::Pass);
+        assert_eq!(answers.check(1, 2, &Answer::Number(42)), Check::Unknown);
+        assert_eq!(
+            answers.check(1, 1, &Answer::Number(41)),
+            Check::Mismatch {
+                expected: "42".to_string(),
+                actual: "41".to_string()
+            }
+        );
+        assert_eq!(diff("ab\ncd\nef", "ab\nxd"), "  ab\n- cd\n+ xd\n- ef");
+    }
+}
@@ -0,0 +1,243 @@
+//! Timing of the solutions for the `bench` command, with a JSON format for saving the results
+//! as a baseline to compare later runs against.
+//!
+//! The JSON is an array with one object per line:
+//!
+//! ```text
+//! [
+//!   {"day": 1, "part": 1, "iterations": 10, "mean_ns": 41210, "median_ns": 40002, "min_ns": 39850},
+//!   {"day": 1, "part": 2, "iterations": 10, "mean_ns": 52371, "median_ns": 51720, "min_ns": 50914}
+//! ]
+//! ```
+//!
+//! [`parse_json`] reads that layout back, and nothing more general.
+
+use std::{
+    error::Error,
+    fmt::Display,
+    time::{Duration, Instant},
+};
+
+use crate::solution::{Solution, SolveError};
+
+/// Summary statistics for one part of one day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timing {
+    pub day: usize,
+    pub part: usize,
+    pub iterations: usize,
+    pub mean: Duration,
+    pub median: Duration,
+    pub min: Duration,
+}
+
+impl Timing {
+    /// Summarises a set of run times. `times` must not be empty.
+    pub fn from_times(day: usize, part: usize, mut times: Vec<Duration>) -> Timing {
+        times.sort();
+        let n = times.len();
+        let median = if n.is_multiple_of(2) {
+            (times[n / 2 - 1] + times[n / 2]) / 2
+        } else {
+            times[n / 2]
+        };
+        Timing {
+            day,
+            part,
+            iterations: n,
+            mean: times.iter().sum::<Duration>() / n as u32,
+            median,
+            min: times[0],
+        }
+    }
+}
+
+/// Runs `solution` on `input` `iterations` times, stopping at the first failure.
+pub fn measure(solution: &Solution, input: &[u8], iterations: usize) -> Result<Timing, SolveError> {
+    let times = (0..iterations.max(1))
+        .map(|_| {
+            let start = Instant::now();
+            (solution.solve)(&mut &input[..])?;
+            Ok(start.elapsed())
+        })
+        .collect::<Result<_, SolveError>>()?;
+    Ok(Timing::from_times(solution.day, solution.part, times))
+}
+
+/// A timing set against the baseline timing for the same day and part.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub timing: Timing,
+    pub baseline: Option<Timing>,
+}
+
+impl Comparison {
+    /// The change in median time from the baseline, in percent.
+    pub fn change(&self) -> Option<f64> {
+        let baseline = self.baseline?.median.as_secs_f64();
+        (baseline > 0.0).then(|| (self.timing.median.as_secs_f64() / baseline - 1.0) * 100.0)
+    }
+
+    /// Whether the median time grew by more than `threshold` percent.
+    pub fn is_regression(&self, threshold: f64) -> bool {
+        self.change().is_some_and(|change| change > threshold)
+    }
+}
+
+pub fn compare(timings: &[Timing], baseline: &[Timing]) -> Vec<Comparison> {
+    timings
+        .iter()
+        .map(|&timing| Comparison {
+            timing,
+            baseline: baseline
+                .iter()
+                .find(|b| b.day == timing.day && b.part == timing.part)
+                .copied(),
+        })
+        .collect()
+}
+
+pub fn to_json(timings: &[Timing]) -> String {
+    let lines: Vec<String> = timings
+        .iter()
+        .map(|t| {
+            format!(
+                r#"  {{"day": {}, "part": {}, "iterations": {}, "mean_ns": {}, "median_ns": {}, "min_ns": {}}}"#,
+                t.day,
+                t.part,
+                t.iterations,
+                t.mean.as_nanos(),
+                t.median.as_nanos(),
+                t.min.as_nanos()
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", lines.join(",\n"))
+}
+
+/// An error reading timings written by [`to_json`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for JsonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for JsonError {}
+
+const KEYS: [&str; 6] = [
+    "day",
+    "part",
+    "iterations",
+    "mean_ns",
+    "median_ns",
+    "min_ns",
+];
+
+fn parse_object(object: &str) -> Result<Timing, String> {
+    let fields = object
+        .strip_prefix('{')
+        .and_then(|object| object.strip_suffix('}'))
+        .ok_or("expected an object")?;
+    let mut values = [None; 6];
+    for field in fields.split(',') {
+        let (key, value) = field.split_once(':').ok_or("expected \"key\": value")?;
+        let key = key.trim().trim_matches('"');
+        let i = KEYS
+            .iter()
+            .position(|&k| k == key)
+            .ok_or_else(|| format!("unknown key {:?}", key))?;
+        let value = value.trim();
+        values[i] = Some(
+            value
+                .parse::<u64>()
+                .map_err(|_| format!("bad value {:?}", value))?,
+        );
+    }
+    let get = |i: usize| values[i].ok_or_else(|| format!("missing {:?}", KEYS[i]));
+    Ok(Timing {
+        day: get(0)? as usize,
+        part: get(1)? as usize,
+        iterations: get(2)? as usize,
+        mean: Duration::from_nanos(get(3)?),
+        median: Duration::from_nanos(get(4)?),
+        min: Duration::from_nanos(get(5)?),
+    })
+}
+
+pub fn parse_json(text: &str) -> Result<Vec<Timing>, JsonError> {
+    let mut timings = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim().trim_end_matches(',');
+        if matches!(line, "" | "[" | "]") {
+            continue;
+        }
+        timings.push(parse_object(line).map_err(|message| JsonError {
+            line: i + 1,
+            message,
+        })?);
+    }
+    Ok(timings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn millis(times: &[u64]) -> Vec<Duration> {
+        times.iter().map(|&ms| Duration::from_millis(ms)).collect()
+    }
+
+    #[test]
+    fn test_statistics() {
+        let timing = Timing::from_times(3, 1, millis(&[9, 2, 4, 5]));
+        assert_eq!(timing.iterations, 4);
+        assert_eq!(timing.mean, Duration::from_millis(5));
+        assert_eq!(
+            timing.median,
+            Duration::from_millis(4) + Duration::from_micros(500)
+        );
+        assert_eq!(timing.min, Duration::from_millis(2));
+        let timing = Timing::from_times(3, 1, millis(&[7, 1, 3]));
+        assert_eq!(timing.median, Duration::from_millis(3));
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let timings = vec![
+            Timing::from_times(1, 1, millis(&[1, 2, 3])),
+            Timing::from_times(16, 2, millis(&[250, 260])),
+        ];
+        assert_eq!(parse_json(&to_json(&timings)), Ok(timings));
+        assert_eq!(
+            parse_json("[\n  {\"day\": 1, \"part\": 1}\n]")
+                .unwrap_err()
+                .to_string(),
+            "line 2: missing \"iterations\""
+        );
+        assert_eq!(
+            parse_json("[\n  {\"day\": x}\n]").unwrap_err().to_string(),
+            "line 2: bad value \"x\""
+        );
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = vec![Timing::from_times(1, 1, millis(&[10]))];
+        let timings = vec![
+            Timing::from_times(1, 1, millis(&[12])),
+            Timing::from_times(1, 2, millis(&[5])),
+        ];
+        let comparisons = compare(&timings, &baseline);
+        assert!((comparisons[0].change().unwrap() - 20.0).abs() < 1e-9);
+        assert!(comparisons[0].is_regression(10.0));
+        assert!(!comparisons[0].is_regression(25.0));
+        assert_eq!(comparisons[1].change(), None);
+        assert!(!comparisons[1].is_regression(0.0));
+    }
+}
@@ -1,26 +1,515 @@
-// use std::env;
-// use std::fs;
-
-// use std::io::BufReader;
-
-// use advent_of_code_2019::*;
-
-fn main() {
-    // let args: Vec<String> = env::args().skip(1).collect();
-    // let days = if args.len() == 1 && args[0] == "all" {
-    //     (1..=25).map(|x| x.to_string()).collect()
-    // } else {
-    //     args
-    // };
-    // for day in days {
-    //     println!("Day {}:", day);
-
-    //     let (star_one, star_two, filename) = get_day(day.parse::<usize>().unwrap());
-    //     let f = fs::File::open(&filename).unwrap();
-    //     let input = BufReader::new(f);
-    //     println!("Star One: {}", star_one(Box::new(input)));
-    //     let f = fs::File::open(&filename).unwrap();
-    //     let input = BufReader::new(f);
-    //     println!("Star Two: {}", star_two(Box::new(input)));
-    // }
+//! Runs the solutions from the command line.
+//!
+//! ```text
+//! main run <day> [--part 1|2] [--input <path>]
+//! main run all
+//! main verify [--data <dir>] [--answers <path>]
+//! main bench [<day|all>] [--part 1|2] [--iterations <n>] [--json <path>]
+//!            [--baseline <path>] [--threshold <percent>]
+//! main render <day> [--input <path>] [--out <path>] [--scale <n>] [--delay <ms>]
+//! ```
+//!
+//! `run` reads input from `data/dayNN.txt` unless `--input` is given, and prints each answer
+//! with the time it took. `verify` runs every solution on the inputs in `--data` (default
+//! `data`) and compares the answers with `--answers` (default `answers.toml` in the same
+//! directory). `bench` times each part over `--iterations` runs (default 10), optionally
+//! writing the timings to `--json` and comparing medians against a `--baseline` written by an
+//! earlier run; slowdowns beyond `--threshold` percent (default 10) count as failures. `render`
+//! draws the days that have pictures (8, 11, 13 and 24) in the terminal, playing animations
+//! with `--delay` milliseconds between frames (default 50), or saves them to `--out`: a `.png`
+//! or `.ppm` file for a still image, or a directory of numbered PNG frames for an animation.
+//! Each pixel is drawn `--scale` times larger (default 1). The exit code is non-zero if
+//! anything failed.
+
+use std::{
+    env, fs, io,
+    panic::{self, AssertUnwindSafe},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use advent_of_code_2019::{
+    answers::{self, Answers, Check},
+    bench::{self, Timing},
+    render::Visual,
+    solution::{self, Answer, Solve, DRAWINGS, SOLUTIONS},
+};
+use itertools::Itertools;
+
+const USAGE: &str = "\
+usage: main run <day|all> [--part 1|2] [--input <path>]
+       main verify [--data <dir>] [--answers <path>]
+       main bench [<day|all>] [--part 1|2] [--iterations <n>] [--json <path>]
+                  [--baseline <path>] [--threshold <percent>]
+       main render <day> [--input <path>] [--out <path>] [--scale <n>] [--delay <ms>]";
+
+#[derive(Debug)]
+enum Command {
+    Run {
+        days: Vec<usize>,
+        parts: Vec<usize>,
+        input: Option<PathBuf>,
+    },
+    Verify {
+        data: PathBuf,
+        answers: Option<PathBuf>,
+    },
+    Bench {
+        days: Vec<usize>,
+        parts: Vec<usize>,
+        iterations: usize,
+        json: Option<PathBuf>,
+        baseline: Option<PathBuf>,
+        threshold: f64,
+    },
+    Render {
+        day: usize,
+        input: Option<PathBuf>,
+        out: Option<PathBuf>,
+        scale: usize,
+        delay: Duration,
+    },
+}
+
+/// Splits `--flag value` pairs.
+fn options(mut rest: &[String]) -> Result<Vec<(&str, &str)>, String> {
+    let mut options = Vec::new();
+    while let Some((flag, tail)) = rest.split_first() {
+        let (value, tail) = tail
+            .split_first()
+            .ok_or_else(|| format!("{} needs a value", flag))?;
+        options.push((flag.as_str(), value.as_str()));
+        rest = tail;
+    }
+    Ok(options)
+}
+
+fn parse_days(day: &str) -> Result<Vec<usize>, String> {
+    match day {
+        "all" => Ok(SOLUTIONS
+            .iter()
+            .map(|solution| solution.day)
+            .dedup()
+            .collect()),
+        day => match day.parse() {
+            Ok(day) if solution::day(day).next().is_some() => Ok(vec![day]),
+            _ => Err(format!("no such day {:?}", day)),
+        },
+    }
+}
+
+fn parse_part(part: &str) -> Result<Vec<usize>, String> {
+    match part {
+        "1" => Ok(vec![1]),
+        "2" => Ok(vec![2]),
+        _ => Err(format!("no such part {:?}", part)),
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("bad number {:?}", value))
+}
+
+fn parse_run(args: &[String]) -> Result<Command, String> {
+    let (day, rest) = args.split_first().ok_or("missing day")?;
+    let days = parse_days(day)?;
+    let mut parts = vec![1, 2];
+    let mut input = None;
+    for (flag, value) in options(rest)? {
+        match flag {
+            "--part" => parts = parse_part(value)?,
+            "--input" => input = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown option {:?}", flag)),
+        }
+    }
+    if input.is_some() && days.len() > 1 {
+        return Err("--input needs a single day".to_string());
+    }
+    Ok(Command::Run { days, parts, input })
+}
+
+fn parse_verify(args: &[String]) -> Result<Command, String> {
+    let mut data = PathBuf::from("data");
+    let mut answers = None;
+    for (flag, value) in options(args)? {
+        match flag {
+            "--data" => data = PathBuf::from(value),
+            "--answers" => answers = Some(PathBuf::from(value)),
+            _ => return Err(format!("unknown option {:?}", flag)),
+        }
+    }
+    Ok(Command::Verify { data, answers })
+}
+
+fn parse_bench(args: &[String]) -> Result<Command, String> {
+    let (days, rest) = match args.split_first() {
+        Some((day, rest)) if !day.starts_with("--") => (parse_days(day)?, rest),
+        _ => (parse_days("all")?, args),
+    };
+    let mut parts = vec![1, 2];
+    let mut iterations = 10;
+    let mut json = None;
+    let mut baseline = None;
+    let mut threshold = 10.0;
+    for (flag, value) in options(rest)? {
+        match flag {
+            "--part" => parts = parse_part(value)?,
+            "--iterations" => iterations = parse_number(value)?,
+            "--json" => json = Some(PathBuf::from(value)),
+            "--baseline" => baseline = Some(PathBuf::from(value)),
+            "--threshold" => threshold = parse_number(value)?,
+            _ => return Err(format!("unknown option {:?}", flag)),
+        }
+    }
+    if iterations == 0 {
+        return Err("--iterations must be at least 1".to_string());
+    }
+    Ok(Command::Bench {
+        days,
+        parts,
+        iterations,
+        json,
+        baseline,
+        threshold,
+    })
+}
+
+fn parse_render(args: &[String]) -> Result<Command, String> {
+    let (day, rest) = args.split_first().ok_or("missing day")?;
+    let day = match parse_number(day)? {
+        day if solution::drawing(day).is_some() => day,
+        day => {
+            let days = DRAWINGS.iter().map(|drawing| drawing.day).join(", ");
+            return Err(format!("day {} has no picture, try one of {}", day, days));
+        }
+    };
+    let mut input = None;
+    let mut out = None;
+    let mut scale = 1;
+    let mut delay = Duration::from_millis(50);
+    for (flag, value) in options(rest)? {
+        match flag {
+            "--input" => input = Some(PathBuf::from(value)),
+            "--out" => out = Some(PathBuf::from(value)),
+            "--scale" => scale = parse_number(value)?,
+            "--delay" => delay = Duration::from_millis(parse_number(value)?),
+            _ => return Err(format!("unknown option {:?}", flag)),
+        }
+    }
+    if scale == 0 {
+        return Err("--scale must be at least 1".to_string());
+    }
+    Ok(Command::Render {
+        day,
+        input,
+        out,
+        scale,
+        delay,
+    })
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = args.split_first().ok_or("missing command")?;
+    match command.as_str() {
+        "run" => parse_run(rest),
+        "verify" => parse_verify(rest),
+        "bench" => parse_bench(rest),
+        "render" => parse_render(rest),
+        _ => Err(format!("unknown command {:?}", command)),
+    }
+}
+
+/// Runs one star, catching a panic so that the remaining stars still run. Failures are
+/// described as text.
+fn run_star(solve: Solve, data: &[u8]) -> (Result<Answer, String>, Duration) {
+    let start = Instant::now();
+    let answer = match panic::catch_unwind(AssertUnwindSafe(|| solve(&mut &data[..]))) {
+        Ok(answer) => answer.map_err(|e| e.to_string()),
+        Err(_) => Err("panicked".to_string()),
+    };
+    (answer, start.elapsed())
+}
+
+/// Runs the requested parts of a day, returning whether they all succeeded.
+fn run_day(day: usize, parts: &[usize], input: Option<&PathBuf>) -> bool {
+    println!("Day {}:", day);
+    let filename = input.cloned().unwrap_or_else(|| solution::input_path(day));
+    let data = match fs::read(&filename) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", filename.display(), e);
+            return false;
+        }
+    };
+    let mut ok = true;
+    for solution in solution::day(day).filter(|solution| parts.contains(&solution.part)) {
+        let label = match solution.part {
+            1 => "Star One",
+            _ => "Star Two",
+        };
+        match run_star(solution.solve, &data) {
+            (Ok(Answer::Text(answer)), elapsed) if answer.contains('\n') => {
+                println!("{} ({:.2?}):\n{}", label, elapsed, answer.trim_end())
+            }
+            (Ok(answer), elapsed) => println!("{}: {} ({:.2?})", label, answer, elapsed),
+            (Err(e), elapsed) => {
+                println!("{}: failed, {} ({:.2?})", label, e, elapsed);
+                ok = false;
+            }
+        }
+    }
+    ok
+}
+
+fn run(days: &[usize], parts: &[usize], input: Option<&PathBuf>) -> ExitCode {
+    let start = Instant::now();
+    let mut failed = 0;
+    for &day in days {
+        if !run_day(day, parts, input) {
+            failed += 1;
+        }
+    }
+    if days.len() > 1 {
+        println!("Total: {:.2?}", start.elapsed());
+    }
+    if failed > 0 {
+        eprintln!("{} day(s) failed", failed);
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Runs every solution on the inputs in `data` and checks the answers against `answers`.
+fn verify(data: &Path, answers: &Answers) -> ExitCode {
+    let (mut passed, mut mismatched, mut failed, mut unknown) = (0, 0, 0, 0);
+    for solution in SOLUTIONS {
+        let label = format!("Day {} part {}", solution.day, solution.part);
+        let filename = solution::input_in(data, solution.day);
+        let input = match fs::read(&filename) {
+            Ok(input) => input,
+            Err(e) => {
+                println!(
+                    "{}: failed, cannot read {}: {}",
+                    label,
+                    filename.display(),
+                    e
+                );
+                failed += 1;
+                continue;
+            }
+        };
+        let answer = match run_star(solution.solve, &input) {
+            (Ok(answer), _) => answer,
+            (Err(e), _) => {
+                println!("{}: failed, {}", label, e);
+                failed += 1;
+                continue;
+            }
+        };
+        match answers.check(solution.day, solution.part, &answer) {
+            Check::Pass => passed += 1,
+            Check::Mismatch { expected, actual } => {
+                println!("{}: mismatch", label);
+                println!("{}", answers::diff(&expected, &actual));
+                mismatched += 1;
+            }
+            Check::Unknown => {
+                println!("{}: no expected answer, got {}", label, answer);
+                unknown += 1;
+            }
+        }
+    }
+    println!(
+        "{} passed, {} mismatched, {} failed, {} without an expected answer",
+        passed, mismatched, failed, unknown
+    );
+    if mismatched + failed > 0 {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Times the requested parts, comparing each with `baseline` and saving them to `json` if
+/// asked.
+fn bench_days(
+    days: &[usize],
+    parts: &[usize],
+    iterations: usize,
+    json: Option<&Path>,
+    baseline: &[Timing],
+    threshold: f64,
+) -> ExitCode {
+    let mut timings = Vec::new();
+    let (mut failed, mut regressions) = (0, 0);
+    for &day in days {
+        let filename = solution::input_path(day);
+        let data = match fs::read(&filename) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!("Cannot read {}: {}", filename.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        for solution in solution::day(day).filter(|solution| parts.contains(&solution.part)) {
+            let label = format!("Day {:>2} part {}", solution.day, solution.part);
+            let measured = panic::catch_unwind(AssertUnwindSafe(|| {
+                bench::measure(solution, &data, iterations)
+            }));
+            let timing = match measured {
+                Ok(Ok(timing)) => timing,
+                Ok(Err(e)) => {
+                    println!("{}: failed, {}", label, e);
+                    failed += 1;
+                    continue;
+                }
+                Err(_) => {
+                    println!("{}: failed, panicked", label);
+                    failed += 1;
+                    continue;
+                }
+            };
+            let comparison = bench::compare(&[timing], baseline)[0];
+            let change = match comparison.change() {
+                Some(change) if comparison.is_regression(threshold) => {
+                    regressions += 1;
+                    format!("  {:+.1}% regression", change)
+                }
+                Some(change) => format!("  {:+.1}%", change),
+                None => String::new(),
+            };
+            println!(
+                "{}: mean {:>10.2?}  median {:>10.2?}  min {:>10.2?}{}",
+                label, timing.mean, timing.median, timing.min, change
+            );
+            timings.push(timing);
+        }
+    }
+    if let Some(path) = json {
+        if let Err(e) = fs::write(path, bench::to_json(&timings)) {
+            eprintln!("Cannot write {}: {}", path.display(), e);
+            failed += 1;
+        }
+    }
+    if regressions > 0 {
+        eprintln!(
+            "{} part(s) slower than the baseline by more than {}%",
+            regressions, threshold
+        );
+    }
+    if failed + regressions > 0 {
+        return ExitCode::FAILURE;
+    }
+    ExitCode::SUCCESS
+}
+
+/// Draws a day in the terminal, or saves it to `out`.
+fn render(
+    day: usize,
+    input: Option<&PathBuf>,
+    out: Option<&Path>,
+    scale: usize,
+    delay: Duration,
+) -> ExitCode {
+    let filename = input.cloned().unwrap_or_else(|| solution::input_path(day));
+    let data = match fs::read(&filename) {
+        Ok(data) => data,
+        Err(e) => {
+            eprintln!("Cannot read {}: {}", filename.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let drawing = solution::drawing(day).expect("checked when parsing the arguments");
+    let visual = match (drawing.draw)(&mut &data[..]) {
+        Ok(visual) => visual,
+        Err(e) => {
+            eprintln!("Day {}: failed, {}", day, e);
+            return ExitCode::FAILURE;
+        }
+    };
+    let written = match (visual, out) {
+        (Visual::Image(image), None) => {
+            print!("{}", image.scale(scale).to_ansi());
+            Ok(())
+        }
+        (Visual::Image(image), Some(path)) => image.scale(scale).save(path),
+        (Visual::Animation(animation), None) => {
+            animation.scale(scale).play(io::stdout().lock(), delay)
+        }
+        // Saving frames to `game.png` would quietly make a directory of that name.
+        (Visual::Animation(_), Some(dir)) if dir.extension().is_some() => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("animations are saved to a directory, not {}", dir.display()),
+        )),
+        (Visual::Animation(animation), Some(dir)) => animation.scale(scale).save_frames(dir, "png"),
+    };
+    match written {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Cannot draw day {}: {}", day, e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match parse_args(&args) {
+        Ok(Command::Run { days, parts, input }) => run(&days, &parts, input.as_ref()),
+        Ok(Command::Verify { data, answers }) => {
+            let path = answers.unwrap_or_else(|| data.join("answers.toml"));
+            let answers = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|text| text.parse::<Answers>().map_err(|e| e.to_string()));
+            match answers {
+                Ok(answers) => verify(&data, &answers),
+                Err(e) => {
+                    eprintln!("{}: {}", path.display(), e);
+                    ExitCode::FAILURE
+                }
+            }
+        }
+        Ok(Command::Bench {
+            days,
+            parts,
+            iterations,
+            json,
+            baseline,
+            threshold,
+        }) => {
+            let baseline = match baseline {
+                Some(path) => match fs::read_to_string(&path)
+                    .map_err(|e| e.to_string())
+                    .and_then(|text| bench::parse_json(&text).map_err(|e| e.to_string()))
+                {
+                    Ok(baseline) => baseline,
+                    Err(e) => {
+                        eprintln!("{}: {}", path.display(), e);
+                        return ExitCode::FAILURE;
+                    }
+                },
+                None => Vec::new(),
+            };
+            bench_days(
+                &days,
+                &parts,
+                iterations,
+                json.as_deref(),
+                &baseline,
+                threshold,
+            )
+        }
+        Ok(Command::Render {
+            day,
+            input,
+            out,
+            scale,
+            delay,
+        }) => render(day, input.as_ref(), out.as_deref(), scale, delay),
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            ExitCode::from(2)
+        }
+    }
 }
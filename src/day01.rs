@@ -1,4 +1,6 @@
-use std::io::BufRead;
+use std::{io::BufRead, str::FromStr};
+
+use crate::{parse, solution::SolveError};
 
 fn process(mass: usize) -> usize {
     mass / 3 - 2
@@ -12,20 +14,21 @@ fn process2(mass: isize) -> isize {
     }
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    input
-        .lines()
-        .map(|l| l.unwrap().parse::<usize>().unwrap())
-        .map(process)
-        .sum()
+fn parse_masses<T: FromStr>(input: impl BufRead) -> Result<Vec<T>, SolveError> {
+    Ok(parse::records(&parse::text(input)?, |line| {
+        parse::number(line, line)
+    })?)
+}
+
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    Ok(parse_masses(input)?.into_iter().map(process).sum())
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    input
-        .lines()
-        .map(|l| l.unwrap().parse::<isize>().unwrap())
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    Ok(parse_masses(input)?
+        .into_iter()
         .map(process2)
-        .sum::<isize>() as usize
+        .sum::<isize>() as usize)
 }
 
 #[cfg(test)]
@@ -1,33 +1,22 @@
 use std::io::BufRead;
 
-use crate::IntCode;
+use crate::{intcode::IntCode, parse, solution::SolveError};
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let input = Vec::new();
     let mut computer = IntCode::new(codes, input);
-    computer.run(1);
-    computer.read(0) as usize
+    computer.run(1)?;
+    Ok(computer.read(0) as usize)
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    if codes.len() < 3 {
+        return Err(SolveError::no_solution(
+            "the program is too short to take a noun and verb",
+        ));
+    }
     // println!("{:?}", codes);
     for noun in 1..100 {
         for verb in 1..100 {
@@ -36,14 +25,14 @@ pub fn star_two(input: impl BufRead) -> usize {
             program[1] = noun;
             program[2] = verb;
             let mut computer = IntCode::new(program, input);
-            computer.run(1);
+            computer.run(1)?;
             if computer.read(0) == 19690720 {
                 // println!("{} {}", noun, verb);
-                return (100 * noun + verb) as usize;
+                return Ok((100 * noun + verb) as usize);
             }
         }
     }
-    unreachable!()
+    Err(SolveError::no_solution("no noun and verb produce 19690720"))
 }
 
 #[cfg(test)]
@@ -54,20 +43,25 @@ mod tests {
     fn test_star_one() {
         {
             let input = b"1,9,10,3,2,3,11,0,99,30,40,50";
-            assert_eq!(star_one(Cursor::new(input)), 3500);
+            assert_eq!(star_one(Cursor::new(input)).unwrap(), 3500);
         }
 
         {
             let input = b"1,0,0,0,99";
-            assert_eq!(star_one(Cursor::new(input)), 2);
+            assert_eq!(star_one(Cursor::new(input)).unwrap(), 2);
         }
 
         {
             let input = b"1,1,1,4,99,5,6,0,99";
-            assert_eq!(star_one(Cursor::new(input)), 30);
+            assert_eq!(star_one(Cursor::new(input)).unwrap(), 30);
         }
     }
 
+    #[test]
+    fn test_star_two_short_program() {
+        assert!(star_two(Cursor::new(b"99")).is_err());
+    }
+
     // #[test]
     // fn test_star_two() {
     //     let input = b"1,9,10,3,2,3,11,0,99,30,40,50";
@@ -1,5 +1,10 @@
 use std::{cmp::PartialEq, io::BufRead};
 
+use crate::{
+    parse::{self, LineError},
+    solution::SolveError,
+};
+
 #[derive(PartialEq)]
 enum LineType {
     Vertical,
@@ -64,41 +69,51 @@ struct Wire {
     lines: Vec<Line>,
 }
 
-fn parse(string: &str) -> Wire {
+fn parse_wire(string: &str) -> Result<Wire, LineError> {
     let mut lines: Vec<Line> = vec![];
     let mut starting_point = Point { x: 0, y: 0 };
     for instruction in string.split(',') {
-        let current_point = match &instruction[0..1] {
-            "R" => Point {
-                x: starting_point.x + instruction[1..].parse::<isize>().unwrap(),
+        let mut chars = instruction.chars();
+        let direction = chars.next();
+        let length = parse::number::<isize>(string, chars.as_str())?;
+        let current_point = match direction {
+            Some('R') => Point {
+                x: starting_point.x + length,
                 y: starting_point.y,
             },
-            "L" => Point {
-                x: starting_point.x - instruction[1..].parse::<isize>().unwrap(),
+            Some('L') => Point {
+                x: starting_point.x - length,
                 y: starting_point.y,
             },
-            "U" => Point {
+            Some('U') => Point {
                 x: starting_point.x,
-                y: starting_point.y + instruction[1..].parse::<isize>().unwrap(),
+                y: starting_point.y + length,
             },
-            "D" => Point {
+            Some('D') => Point {
                 x: starting_point.x,
-                y: starting_point.y - instruction[1..].parse::<isize>().unwrap(),
+                y: starting_point.y - length,
             },
-            _ => panic!("{}", instruction),
+            _ => {
+                return Err(LineError::at(
+                    string,
+                    instruction,
+                    format!("expected a direction R, L, U or D in {:?}", instruction),
+                ))
+            }
         };
         lines.push(Line(starting_point.clone(), current_point.clone()));
         starting_point = current_point;
     }
     // println!("{}", string);
-    Wire { lines }
+    Ok(Wire { lines })
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let wires: Vec<Wire> = input
-        .lines()
-        .map(|line| parse(line.unwrap().as_str()))
-        .collect();
+fn parse_wires(input: impl BufRead) -> Result<Vec<Wire>, SolveError> {
+    Ok(parse::records(&parse::text(input)?, parse_wire)?)
+}
+
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let wires = parse_wires(input)?;
     let mut intersections = vec![];
     // TODO: Change to be not as O(N^2)
     for i in 0..wires.len() {
@@ -120,17 +135,14 @@ pub fn star_one(input: impl BufRead) -> usize {
     {
         Some(val) => {
             println!("{:?} {}", val, val.x.abs() + val.y.abs());
-            (val.x.abs() + val.y.abs()) as usize
+            Ok((val.x.abs() + val.y.abs()) as usize)
         }
-        None => unreachable!(),
+        None => Err(SolveError::no_solution("the wires never cross")),
     }
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let wires: Vec<Wire> = input
-        .lines()
-        .map(|line| parse(line.unwrap().as_str()))
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let wires = parse_wires(input)?;
     let mut intersections = vec![];
     // TODO: Change to be not as O(N^2)
     for i in 0..wires.len() {
@@ -154,8 +166,11 @@ pub fn star_two(input: impl BufRead) -> usize {
     }
     // println!("Wires: {:?}", wires);
     // println!("Intersections: {:?}", intersections);
-    let (_point, x, y) = intersections.into_iter().min_by_key(|a| a.1 + a.2).unwrap();
-    (x.abs() + y.abs()) as usize
+    let (_point, x, y) = intersections
+        .into_iter()
+        .min_by_key(|a| a.1 + a.2)
+        .ok_or_else(|| SolveError::no_solution("the wires never cross"))?;
+    Ok((x.abs() + y.abs()) as usize)
 }
 
 #[cfg(test)]
@@ -1,10 +1,12 @@
 use std::io::BufRead;
 
+use crate::solution::SolveError;
+
 const MAX_VALUE: i32 = 824795;
 const MIN_VALUE: i32 = 278384;
 
-pub fn star_one(_input: impl BufRead) -> usize {
-    (MIN_VALUE..=MAX_VALUE)
+pub fn star_one(_input: impl BufRead) -> Result<usize, SolveError> {
+    Ok((MIN_VALUE..=MAX_VALUE)
         // It is a six-digit number.
         .filter(|d| d.to_string().len() == 6)
         // Two adjacent digits are the same (like 22 in 122345).
@@ -23,7 +25,7 @@ pub fn star_one(_input: impl BufRead) -> usize {
                 .windows(2)
                 .all(|a| a[0] <= a[1])
         })
-        .count()
+        .count())
 }
 
 fn check(d: &i32) -> bool {
@@ -50,13 +52,13 @@ fn check(d: &i32) -> bool {
 // Two adjacent digits are the same (like 22 in 122345).
 // Going from left to right, the digits never decrease; they only ever increase or stay the same (like 111123 or 135679).
 
-pub fn star_two(_input: impl BufRead) -> usize {
-    (MIN_VALUE..=MAX_VALUE)
+pub fn star_two(_input: impl BufRead) -> Result<usize, SolveError> {
+    Ok((MIN_VALUE..=MAX_VALUE)
         // It is a six-digit number.
         .filter(|d| d.to_string().len() == 6)
         // Two adjacent digits are the same (like 22 in 122345).
         .filter(check)
-        .count()
+        .count())
 }
 
 #[cfg(test)]
@@ -1,69 +1,51 @@
-use crate::IntCode;
+use crate::{intcode::IntCode, parse, solution::SolveError};
 
 use std::io::BufRead;
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let input = vec![1];
-    let mut computer = IntCode::new(codes, input);
-    let _state = computer.run(0);
+    let mut computer = IntCode::builder(codes).input(input).strict(true).build();
+    let _state = computer.run(0)?;
     let output = computer.take_output();
-    output.into_iter().find(|&x| x != 0).unwrap() as usize
+    let code = output
+        .into_iter()
+        .find(|&x| x != 0)
+        .ok_or_else(|| SolveError::no_solution("no diagnostic code in the output"))?;
+    Ok(code as usize)
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let input = vec![5];
-    let mut computer = IntCode::new(codes, input);
-    let _state = computer.run(0);
+    let mut computer = IntCode::builder(codes).input(input).strict(true).build();
+    let _state = computer.run(0)?;
     let output = computer.take_output();
 
-    output.into_iter().find(|&x| x != 0).unwrap() as usize
+    let code = output
+        .into_iter()
+        .find(|&x| x != 0)
+        .ok_or_else(|| SolveError::no_solution("no diagnostic code in the output"))?;
+    Ok(code as usize)
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::IntCode;
+    use super::*;
+    use std::io::Cursor;
 
-    #[test]
-    fn test_star_one() {}
-
-    #[test]
-    fn test_star_two() {}
+    // The opcode and mode samples are covered by `intcode::conformance`.
 
     #[test]
-    fn test_program() {
-        let input = vec![1];
-        let program = vec![1101, 100, -1, 4, 0];
-        let mut computer = IntCode::new(program, input);
-        computer.run(1);
-        assert_eq!(computer.get_program(), &vec![1101, 100, -1, 4, 99]);
+    fn test_star_one() {
+        // Echoes the input.
+        assert_eq!(star_one(Cursor::new(b"3,0,4,0,99")).unwrap(), 1);
     }
 
     #[test]
-    fn test_program_2() {
-        let input = vec![1];
-        let program = vec![1002, 4, 3, 4, 33];
-        let mut computer = IntCode::new(program, input);
-        computer.run(0);
-        assert_eq!(computer.get_program(), &vec![1002, 4, 3, 4, 99]);
+    fn test_star_two() {
+        // Outputs 999 for input below 8, 1000 for 8 and 1001 above.
+        let input = b"3,21,1008,21,8,20,1005,20,22,107,8,21,20,1006,20,31,1106,0,36,98,0,0,1002,21,125,20,4,20,1105,1,46,104,999,1105,1,46,1101,1000,1,20,4,20,1105,1,46,98,99";
+        assert_eq!(star_two(Cursor::new(input)).unwrap(), 999);
     }
 }
@@ -1,5 +1,22 @@
 use std::{collections::HashMap, io::BufRead};
 
+use crate::{
+    parse::{self, LineError, ParseError},
+    solution::SolveError,
+};
+
+/// Maps each object to the one it orbits.
+fn parse_orbits(text: &str) -> Result<HashMap<&str, &str>, ParseError> {
+    let orbits = parse::records(text, |line| {
+        line.split_once(')')
+            .ok_or_else(|| LineError::from("expected CENTER)OBJECT"))
+    })?;
+    Ok(orbits
+        .into_iter()
+        .map(|(center, object)| (object, center))
+        .collect())
+}
+
 fn get_distance(objects: &HashMap<&str, &str>, key: &str) -> usize {
     objects
         .get(key)
@@ -7,24 +24,16 @@ fn get_distance(objects: &HashMap<&str, &str>, key: &str) -> usize {
         .unwrap_or(0)
 }
 
-pub fn star_one(mut input: impl BufRead) -> usize {
-    // FIXME Change to not reading into a string.
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let objects = buf
-        .lines()
-        .map(|l| l.split(')').collect::<Vec<&str>>())
-        .fold(HashMap::new(), |mut objects, orbit| {
-            objects.insert(orbit[1], orbit[0]);
-            objects
-        });
-    objects
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let text = parse::text(input)?;
+    let objects = parse_orbits(&text)?;
+    Ok(objects
         .keys()
         .map(|key| get_distance(&objects, key))
-        .sum::<usize>()
+        .sum::<usize>())
 }
 
-fn get_shortest_distance(objects: &HashMap<&str, &str>, start: &str, end: &str) -> usize {
+fn get_shortest_distance(objects: &HashMap<&str, &str>, start: &str, end: &str) -> Option<usize> {
     // Go back to COM from START
     let mut path = vec![start];
     while let Some(object) = objects.get(path.last().unwrap()) {
@@ -41,23 +50,34 @@ fn get_shortest_distance(objects: &HashMap<&str, &str>, start: &str, end: &str)
             path2.reverse();
             println!("{:?}", path3);
             println!("{:?}", path2);
-            return path3.len() + path2.len() - 3;
+            return Some(path3.len() + path2.len() - 3);
         }
         current = object;
     }
-    panic!();
+    None
 }
 
-pub fn star_two(mut input: impl BufRead) -> usize {
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let objects = buf
-        .lines()
-        .map(|l| l.split(')').collect::<Vec<&str>>())
-        .fold(HashMap::new(), |mut objects, orbit| {
-            objects.insert(orbit[1], orbit[0]);
-            objects
-        });
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let text = parse::text(input)?;
+    let objects = parse_orbits(&text)?;
 
     get_shortest_distance(&objects, "YOU", "SAN")
+        .ok_or_else(|| SolveError::no_solution("YOU and SAN orbit nothing in common"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            star_one(&b"COM)B\nB-C\n"[..]).unwrap_err().to_string(),
+            "parsing input: line 2, column 1: expected CENTER)OBJECT"
+        );
+        assert!(matches!(
+            star_two(&b"COM)B\nB)YOU\n"[..]),
+            Err(SolveError::NoSolution(_))
+        ));
+    }
 }
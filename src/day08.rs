@@ -1,5 +1,12 @@
 use std::io::BufRead;
 
+use crate::{
+    grid::{Grid, Point},
+    parse::{self, ParseError},
+    render::{Image, Rgb},
+    solution::SolveError,
+};
+
 #[derive(Debug, PartialEq)]
 struct Layer(Vec<u8>);
 
@@ -27,26 +34,28 @@ impl Picture {
             .map(|v| Layer(v.to_owned()))
     }
 
-    fn get_pixel(&self, y: usize, x: usize) -> char {
+    /// Stacks the layers: each pixel takes the colour of the first layer that isn't
+    /// transparent (2) there.
+    fn decode(&self) -> Grid<u8> {
+        let mut image = Grid::new(self.width, self.height, 2);
         for l in self.layers() {
-            match l.0[self.width * y + x] {
-                0 => return '0',
-                1 => return '1',
-                _ => {}
+            for (i, &colour) in l.0.iter().enumerate() {
+                let pixel =
+                    &mut image[Point::new((i % self.width) as isize, (i / self.width) as isize)];
+                if *pixel == 2 {
+                    *pixel = colour;
+                }
             }
         }
-        unreachable!()
+        image
     }
 
     fn get_picture(&self) -> String {
-        let mut v = Vec::with_capacity(self.width * self.height);
-        for y in 0..self.height {
-            for x in 0..self.width {
-                v.push(self.get_pixel(y, x))
-            }
-            v.push('\n');
-        }
-        v.into_iter().collect()
+        self.decode().render(|pixel| match pixel {
+            0 => '0',
+            1 => '1',
+            _ => ' ',
+        }) + "\n"
     }
 }
 
@@ -64,30 +73,48 @@ fn process(data: Vec<u8>, width: usize, height: usize) -> usize {
     layer.get_hash()
 }
 
-pub fn star_one(mut input: impl BufRead) -> usize {
-    let mut input_text = String::new();
-    input.read_to_string(&mut input_text).unwrap();
-    let data: Vec<u8> = input_text
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect();
-    process(data, 25, 6)
+/// Reads the image data, which must be a whole number of 25 by 6 layers.
+fn read_image(input: impl BufRead) -> Result<Vec<u8>, SolveError> {
+    let data = parse::digits(input)?;
+    if data.is_empty() || !data.len().is_multiple_of(25 * 6) {
+        return Err(ParseError::new(
+            1,
+            data.len() + 1,
+            format!("expected whole layers of 150 digits, found {}", data.len()),
+        )
+        .into());
+    }
+    Ok(data)
+}
+
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    Ok(process(read_image(input)?, 25, 6))
+}
+
+/// The decoded picture, with black and white pixels and any left transparent in grey.
+pub fn render(input: impl BufRead) -> Result<Image, SolveError> {
+    let data = read_image(input)?;
+    let p = Picture {
+        data,
+        width: 25,
+        height: 6,
+    };
+    Ok(Image::from_grid(&p.decode(), |pixel| match pixel {
+        0 => Rgb::BLACK,
+        1 => Rgb::WHITE,
+        _ => Rgb::GREY,
+    }))
 }
 
-pub fn star_two(mut input: impl BufRead) -> String {
-    let mut input_text = String::new();
-    input.read_to_string(&mut input_text).unwrap();
-    let data: Vec<u8> = input_text
-        .chars()
-        .map(|c| c.to_digit(10).unwrap() as u8)
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<String, SolveError> {
+    let data = read_image(input)?;
     let p = Picture {
         data,
         width: 25,
         height: 6,
     };
     println!("{}", p.get_picture());
-    "CFLUL".into()
+    Ok("CFLUL".into())
 }
 
 #[cfg(test)]
@@ -96,19 +123,13 @@ mod tests {
 
     #[test]
     fn test_star_one() {
-        let data: Vec<u8> = "123456789012"
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as u8)
-            .collect();
+        let data = parse::digits("123456789012".as_bytes()).unwrap();
         assert_eq!(process(data, 3, 2), 1);
     }
 
     #[test]
     fn test_star_two() {
-        let data: Vec<u8> = "0222112222120000"
-            .chars()
-            .map(|c| c.to_digit(10).unwrap() as u8)
-            .collect();
+        let data = parse::digits("0222112222120000".as_bytes()).unwrap();
         let p = Picture {
             data,
             width: 2,
@@ -5,25 +5,29 @@ use std::{
     io::BufRead,
 };
 
+use crate::{
+    grid::{Grid, Point},
+    parse::ParseError,
+    solution::SolveError,
+};
+
 enum Space {
     Asteroid,
     Empty,
 }
 
-impl From<char> for Space {
-    fn from(c: char) -> Self {
+impl Space {
+    fn from_char(c: char) -> Option<Space> {
         match c {
-            '#' => Space::Asteroid,
-            '.' => Space::Empty,
-            x => panic!("Unable to map char {} to space", x),
+            '#' => Some(Space::Asteroid),
+            '.' => Some(Space::Empty),
+            _ => None,
         }
     }
 }
 
-fn convert_map(data: impl BufRead) -> Vec<Vec<Space>> {
-    data.lines()
-        .map(|line| line.unwrap().chars().map(Space::from).collect())
-        .collect()
+fn convert_map(data: impl BufRead) -> Result<Grid<Space>, ParseError> {
+    Grid::parse(data, Space::from_char)
 }
 
 fn calculate_angle(origin_x: usize, origin_y: usize, point_x: usize, point_y: usize) -> isize {
@@ -34,48 +38,44 @@ fn calculate_angle(origin_x: usize, origin_y: usize, point_x: usize, point_y: us
 }
 
 fn get_visible_from(
-    map: &[Vec<Space>],
+    map: &Grid<Space>,
     i: usize,
     j: usize,
 ) -> HashMap<isize, Vec<(usize, usize, usize)>> {
     let mut angles = HashMap::new();
-    for (y, row) in map.iter().enumerate() {
-        for (x, cell) in row.iter().enumerate() {
-            if !(y == j && x == i) {
-                match cell {
-                    Space::Asteroid => {
-                        let angle = calculate_angle(i, j, x, y);
-                        // assuming it just for ordering not actual distance calcs
-                        let distance = ((x as isize - i as isize).pow(2)
-                            + (y as isize - j as isize).pow(2))
-                            as usize;
-                        let distances = angles.entry(angle).or_insert_with(Vec::new);
-                        distances.push((x, y, distance));
-                    }
-                    Space::Empty => {}
+    for (point, cell) in map.iter() {
+        let (x, y) = (point.x as usize, point.y as usize);
+        if !(y == j && x == i) {
+            match cell {
+                Space::Asteroid => {
+                    let angle = calculate_angle(i, j, x, y);
+                    // assuming it just for ordering not actual distance calcs
+                    let distance = ((x as isize - i as isize).pow(2)
+                        + (y as isize - j as isize).pow(2))
+                        as usize;
+                    let distances = angles.entry(angle).or_insert_with(Vec::new);
+                    distances.push((x, y, distance));
                 }
+                Space::Empty => {}
             }
         }
     }
     angles
 }
 
-fn get_max_visible(map: &[Vec<Space>]) -> Option<(usize, usize, usize)> {
+fn get_max_visible(map: &Grid<Space>) -> Option<(usize, usize, usize)> {
     map.iter()
-        .enumerate()
-        .filter_map(|(y, row)| {
-            row.iter()
-                .enumerate()
-                .filter_map(|(x, cell)| match cell {
-                    Space::Asteroid => Some((x, y, get_visible_from(map, x, y).len())),
-                    Space::Empty => None,
-                })
-                .max_by_key(|x| x.2)
+        .filter_map(|(Point { x, y }, cell)| match cell {
+            Space::Asteroid => {
+                let (x, y) = (x as usize, y as usize);
+                Some((x, y, get_visible_from(map, x, y).len()))
+            }
+            Space::Empty => None,
         })
         .max_by_key(|x| x.2)
 }
 
-fn spin(map: &[Vec<Space>], x: usize, y: usize) -> Vec<(usize, usize, usize)> {
+fn spin(map: &Grid<Space>, x: usize, y: usize) -> Vec<(usize, usize, usize)> {
     let mut angles: VecDeque<_> = get_visible_from(map, x, y).into_iter().collect();
     angles.make_contiguous().sort_by_key(|x| x.0);
     // dbg!(&angles);
@@ -91,22 +91,21 @@ fn spin(map: &[Vec<Space>], x: usize, y: usize) -> Vec<(usize, usize, usize)> {
     destroyed
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let map = convert_map(input);
-    get_max_visible(&map).unwrap().2
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let map = convert_map(input)?;
+    let (_x, _y, visible) =
+        get_max_visible(&map).ok_or_else(|| SolveError::no_solution("there are no asteroids"))?;
+    Ok(visible)
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let map = convert_map(input);
-    let (x, y, _visible) = get_max_visible(&map).unwrap();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let map = convert_map(input)?;
+    let (x, y, _visible) =
+        get_max_visible(&map).ok_or_else(|| SolveError::no_solution("there are no asteroids"))?;
     spin(&map, x, y)
         .get(199)
-        .map(|c| {
-            dbg!(&c);
-            let (x, y, _d) = c;
-            100 * x + y
-        })
-        .unwrap()
+        .map(|&(x, y, _d)| 100 * x + y)
+        .ok_or_else(|| SolveError::no_solution("fewer than 200 asteroids are vaporized"))
 }
 
 #[cfg(test)]
@@ -143,7 +142,7 @@ mod tests {
 #####
 ....#
 ...##";
-        let map = convert_map(Cursor::new(data));
+        let map = convert_map(Cursor::new(data)).unwrap();
         assert_eq!(get_visible_from(&map, 3, 4).len(), 8)
     }
 
@@ -159,13 +158,13 @@ mod tests {
 .##.#..###
 ##...#..#.
 .#....####";
-        let res = star_one(Cursor::new(data));
+        let res = star_one(Cursor::new(data)).unwrap();
         assert_eq!(res, 33);
     }
 
     #[test]
     fn test_spin() {
-        let map = convert_map(Cursor::new(DATA3));
+        let map = convert_map(Cursor::new(DATA3)).unwrap();
         let destroyed = spin(&map, 11, 13);
         let (x, y, _d) = destroyed[0];
         assert_eq!((x, y), (11, 12));
@@ -188,11 +187,8 @@ mod tests {
 ##...#...#.#####.
 ..#.....#...###..
 ..#.#.....#....##";
-        let map = convert_map(Cursor::new(data));
+        let map = convert_map(Cursor::new(data)).unwrap();
         let destroyed = spin(&map, 8, 3);
-        for i in &destroyed {
-            println!("{:?}", i);
-        }
         let expected = vec![
             (8, 1),
             (9, 0),
@@ -226,7 +222,7 @@ mod tests {
 
     #[test]
     fn test_star_two() {
-        let res = star_two(Cursor::new(DATA3));
+        let res = star_two(Cursor::new(DATA3)).unwrap();
         assert_eq!(res, 802);
     }
 }
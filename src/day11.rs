@@ -1,6 +1,12 @@
-use std::{collections::HashMap, fmt::Display, io::BufRead};
+use std::io::BufRead;
 
-use crate::{IntCode, IntCodeState};
+use crate::{
+    grid::{Point, SparseGrid},
+    intcode::{io::Controller, IntCode},
+    parse,
+    render::{Image, Rgb},
+    solution::SolveError,
+};
 
 #[derive(Debug, Copy, Clone, Default)]
 enum Colour {
@@ -9,105 +15,116 @@ enum Colour {
     White = 1,
 }
 
-impl From<isize> for Colour {
-    fn from(i: isize) -> Self {
+impl TryFrom<isize> for Colour {
+    type Error = String;
+
+    fn try_from(i: isize) -> Result<Self, Self::Error> {
         match i {
-            0 => Colour::Black,
-            1 => Colour::White,
-            x => panic!("Unable to convert {} to colour", x),
+            0 => Ok(Colour::Black),
+            1 => Ok(Colour::White),
+            x => Err(format!("the program painted an unknown colour {}", x)),
         }
     }
 }
 
-impl Display for Colour {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl Colour {
+    fn symbol(&self) -> char {
+        match self {
+            Colour::Black => '.',
+            Colour::White => '#',
+        }
+    }
+
+    fn rgb(&self) -> Rgb {
         match self {
-            Colour::Black => write!(f, "."),
-            Colour::White => write!(f, "#"),
+            Colour::Black => Rgb::BLACK,
+            Colour::White => Rgb::WHITE,
         }
     }
 }
 
-fn paint(codes: Vec<isize>, start_panel: Colour) -> HashMap<(isize, isize), Colour> {
-    let mut location = (0, 0);
-    let mut direction = 0_isize;
-    let mut panel = HashMap::new();
-    panel.insert(location, start_panel);
-
-    let input = vec![*panel.get(&location).unwrap_or(&Colour::default()) as isize];
-    let mut computer = IntCode::new(codes, input);
-    let mut halted = false;
-    while !halted {
-        let input = vec![*panel.get(&location).unwrap_or(&Colour::default()) as isize];
-        computer.set_input(input);
-        let state = computer.run(1);
-        if let IntCodeState::Halted(_) = state {
-            break;
+/// Headings in the order a left turn steps through them.
+const HEADINGS: [Point; 4] = [Point::UP, Point::LEFT, Point::DOWN, Point::RIGHT];
+
+struct Robot {
+    panel: SparseGrid<Colour>,
+    location: Point,
+    /// Index into [`HEADINGS`].
+    direction: usize,
+    painted: bool,
+    /// Set when the program sends something the robot can't follow, which stops the robot.
+    fault: Option<String>,
+}
+
+impl Controller for Robot {
+    fn input(&mut self) -> Option<isize> {
+        if self.fault.is_some() {
+            return None;
         }
-        panel.insert(location, Colour::from(computer.take_output()[0]));
-        let input = vec![*panel.get(&location).unwrap_or(&Colour::default()) as isize];
-        computer.set_input(input);
-        // Direction
-        let state = computer.run(1);
-        direction = match computer.take_output()[0] {
-            0 => (direction + 90).rem_euclid(360),
-            1 => (direction - 90).rem_euclid(360),
-            x => panic!("Bad direction: {}", x),
-        };
-        location = match direction {
-            0 => (location.0, location.1 + 1),   // Up
-            90 => (location.0 + 1, location.1),  // Left
-            180 => (location.0, location.1 - 1), // Down
-            270 => (location.0 - 1, location.1), // Right
-            x => panic!("Invalid direction: {}", x),
+        Some(*self.panel.get(self.location).unwrap_or(&Colour::default()) as isize)
+    }
+
+    // Outputs alternate between the colour to paint and the direction to turn.
+    fn output(&mut self, value: isize) {
+        if self.fault.is_some() {
+            return;
+        }
+        if !self.painted {
+            match Colour::try_from(value) {
+                Ok(colour) => self.panel.insert(self.location, colour),
+                Err(fault) => return self.fault = Some(fault),
+            };
+            self.painted = true;
+            return;
+        }
+        self.painted = false;
+        self.direction = match value {
+            0 => (self.direction + 1) % HEADINGS.len(),
+            1 => (self.direction + HEADINGS.len() - 1) % HEADINGS.len(),
+            x => return self.fault = Some(format!("the program made an unknown turn {}", x)),
         };
-        halted = matches!(state, IntCodeState::Halted(_));
+        self.location += HEADINGS[self.direction];
+    }
+}
+
+fn paint(codes: Vec<isize>, start_panel: Colour) -> Result<SparseGrid<Colour>, SolveError> {
+    let mut robot = Robot {
+        panel: SparseGrid::new(),
+        location: Point::ORIGIN,
+        direction: 0,
+        painted: false,
+        fault: None,
+    };
+    robot.panel.insert(robot.location, start_panel);
+
+    let mut computer = IntCode::new(codes, vec![]);
+    computer.run_controller(&mut robot)?;
+    match robot.fault {
+        Some(fault) => Err(SolveError::NoSolution(fault)),
+        None => Ok(robot.panel),
     }
-    panel
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-    let panel = paint(codes, Colour::Black);
-    panel.len()
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    let panel = paint(codes, Colour::Black)?;
+    Ok(panel.len())
 }
 
-pub fn star_two(input: impl BufRead) -> String {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-
-    let panel = paint(codes, Colour::White);
-    let max_x = panel.keys().max_by_key(|k| k.0).unwrap().0;
-    let max_y = panel.keys().max_by_key(|k| k.1).unwrap().1;
-
-    let min_x = panel.keys().min_by_key(|k| k.0).unwrap().0;
-    let min_y = panel.keys().min_by_key(|k| k.1).unwrap().1;
-
-    (min_y..=max_y)
-        .rev()
-        .map(|y| {
-            (min_x..=max_x)
-                .rev()
-                .map(|x| panel.get(&(x, y)).unwrap_or(&Colour::Black).to_string())
-                .collect::<String>()
-        })
-        .collect::<Vec<String>>()
-        .join("\n")
+/// The hull as painted for part two, starting on a white panel.
+pub fn render(input: impl BufRead) -> Result<Image, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    let panel = paint(codes, Colour::White)?;
+    Ok(Image::from_sparse(
+        &panel,
+        Colour::default().rgb(),
+        Colour::rgb,
+    ))
+}
+
+pub fn star_two(input: impl BufRead) -> Result<String, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+
+    let panel = paint(codes, Colour::White)?;
+    Ok(panel.render(Colour::default().symbol(), Colour::symbol))
 }
@@ -6,8 +6,7 @@ use nom::{
     bytes::complete::tag,
     character::complete::one_of,
     combinator::{map_res, opt, recognize},
-    multi::separated_list1,
-    sequence::delimited,
+    sequence::{delimited, preceded},
     IResult, Parser,
 };
 use num::Integer;
@@ -16,6 +15,11 @@ use std::ops::Add;
 use std::ops::AddAssign;
 use std::str::FromStr;
 
+use crate::{
+    parse::{self, LineError, ParseError},
+    solution::SolveError,
+};
+
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 struct Position {
     x: isize,
@@ -23,16 +27,20 @@ struct Position {
     z: isize,
 }
 
-fn parse_position(s: &str) -> IResult<&str, Position> {
+fn parse_coord(s: &str) -> IResult<&str, (char, isize)> {
     let parse_decimal_str = recognize((opt(tag("-")), many1(one_of("0123456789"))));
     let decimal = map_res(parse_decimal_str, |s: &str| s.parse::<isize>());
-    let coord = separated_pair(anychar, char('='), decimal);
-    let coords = separated_list1(tag(", "), coord);
-    let (_rest, data) = delimited(char('<'), coords, char('>')).parse(s)?;
-    let (_k, x) = data[0];
-    let (_k, y) = data[1];
-    let (_k, z) = data[2];
-    Ok(("", Position { x, y, z }))
+    separated_pair(anychar, char('='), decimal).parse(s)
+}
+
+fn parse_position(s: &str) -> IResult<&str, Position> {
+    let coords = (
+        parse_coord,
+        preceded(tag(", "), parse_coord),
+        preceded(tag(", "), parse_coord),
+    );
+    let (rest, ((_, x), (_, y), (_, z))) = delimited(char('<'), coords, char('>')).parse(s)?;
+    Ok((rest, Position { x, y, z }))
 }
 
 impl Position {
@@ -163,17 +171,17 @@ impl System {
 }
 
 impl FromStr for System {
-    type Err = String;
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let positions: Vec<Position> = s
-            .lines()
-            .map(|line| {
-                // println!("{}", &v);
-                let (_, position) = parse_position(line.trim()).unwrap();
-                position
-            })
-            .collect();
+        let positions = parse::records(s, |line| {
+            let position = line.trim_start();
+            match parse_position(position) {
+                Ok(("", position)) => Ok(position),
+                Ok((rest, _)) => Err(LineError::at(line, rest, "expected the end of the line")),
+                Err(e) => Err(LineError::nom(line, e, "<x=X, y=Y, z=Z>")),
+            }
+        })?;
         let velocities = positions
             .iter()
             .map(|_x| Velocity { x: 0, y: 0, z: 0 })
@@ -185,14 +193,12 @@ impl FromStr for System {
     }
 }
 
-pub fn star_one(mut input: impl BufRead) -> usize {
-    let mut input_text = String::new();
-    input.read_to_string(&mut input_text).unwrap();
-    let mut system = input_text.parse::<System>().unwrap();
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let mut system = parse::text(input)?.parse::<System>()?;
     for _x in 0..1000 {
         system.step();
     }
-    system.energy() as usize
+    Ok(system.energy() as usize)
 }
 
 fn get_axis(velocity: &Velocity, axis: usize) -> isize {
@@ -213,10 +219,8 @@ fn get_axis_position(position: &Position, axis: usize) -> isize {
     }
 }
 
-pub fn star_two(mut input: impl BufRead) -> usize {
-    let mut input_text = String::new();
-    input.read_to_string(&mut input_text).unwrap();
-    let mut system = input_text.parse::<System>().unwrap();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let mut system = parse::text(input)?.parse::<System>()?;
     let starting_system = system.clone();
     // Period of complete change of each axis.
     let mut periods = vec![None; 3];
@@ -240,9 +244,9 @@ pub fn star_two(mut input: impl BufRead) -> usize {
     }
 
     let first = periods[0].unwrap();
-    periods
+    Ok(periods
         .into_iter()
-        .fold(first, |lcm, p| lcm.lcm(&p.unwrap()))
+        .fold(first, |lcm, p| lcm.lcm(&p.unwrap())))
 }
 
 #[cfg(test)]
@@ -268,6 +272,14 @@ mod tests {
         let input = "<x=-1, y=0, z=2>";
         let expected = Ok(("", Position { x: -1, y: 0, z: 2 }));
         assert_eq!(parse_position(input), expected);
+        assert_eq!(
+            "<x=1, y=2, z=3>\n<x=1, y=2>"
+                .parse::<System>()
+                .err()
+                .unwrap()
+                .to_string(),
+            "line 2, column 10: expected <x=X, y=Y, z=Z>"
+        );
     }
 
     #[test]
@@ -374,6 +386,6 @@ mod tests {
 
     #[test]
     fn test_star_two() {
-        assert_eq!(star_two(Cursor::new(INPUT)), 2772);
+        assert_eq!(star_two(Cursor::new(INPUT)).unwrap(), 2772);
     }
 }
@@ -1,67 +1,67 @@
-use std::{collections::HashMap, io::BufRead};
+use std::{collections::VecDeque, io::BufRead};
 
-use crate::{IntCode, IntCodeState};
+use crate::{
+    grid::{Point, SparseGrid},
+    intcode::{io::Controller, IntCode},
+    parse,
+    render::{Animation, Image, Rgb},
+    solution::SolveError,
+};
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let mut computer = IntCode::new(codes, vec![]);
-    let mut halted = false;
-    let mut screen = HashMap::new();
-    while !halted {
-        let state = computer.run(3);
-        halted = matches!(state, IntCodeState::Halted(_));
-        if !halted {
-            let output = computer.take_output();
-            screen.insert((output[0], output[1]), output[2]);
-        }
+    let mut output = Vec::new();
+    computer.run_io(&mut VecDeque::new(), &mut output)?;
+    let screen: SparseGrid<_> = output
+        .chunks_exact(3)
+        .map(|tile| (Point::new(tile[0], tile[1]), tile[2]))
+        .collect();
+    Ok(screen.values().filter(|&&x| x == 2).count())
+}
+
+#[derive(Default)]
+struct Arcade {
+    buffer: Vec<isize>,
+    screen: SparseGrid<isize>,
+    score: isize,
+    ball_x: isize,
+    paddle_x: isize,
+    /// When set, the screen is recorded each time the game asks for the joystick.
+    frames: Option<Animation>,
+}
+
+/// The colour of each tile: empty, wall, block, paddle and ball.
+fn tile_colour(tile: &isize) -> Rgb {
+    match tile {
+        1 => Rgb::GREY,
+        2 => Rgb::BLUE,
+        3 => Rgb::WHITE,
+        4 => Rgb::YELLOW,
+        _ => Rgb::BLACK,
     }
-    screen.values().filter(|&&x| x == 2).count()
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let mut codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-    // Memory address 0 represents the number of quarters that have been inserted; set it to 2 to play for free.
-    codes[0] = 2;
-    let mut computer = IntCode::new(codes, vec![]);
-    let mut halted = false;
-    let mut screen = HashMap::new();
-    let mut score = 0;
-    let mut ball_x = 0;
-    let mut paddle_x = 0;
-    while !halted {
+impl Controller for Arcade {
+    fn input(&mut self) -> Option<isize> {
+        if let Some(frames) = &mut self.frames {
+            frames.push(Image::from_sparse(&self.screen, Rgb::BLACK, tile_colour));
+        }
         // Move paddle to be under the ball.
-        let input = vec![ball_x - paddle_x];
-        computer.set_input(input);
-        let state = computer.run(3);
-        halted = matches!(state, IntCodeState::Halted(_));
-        if halted {
-            break;
+        Some((self.ball_x - self.paddle_x).signum())
+    }
+
+    fn output(&mut self, value: isize) {
+        self.buffer.push(value);
+        if self.buffer.len() < 3 {
+            return;
         }
-        let output = computer.take_output();
-        match (output[0], output[1], output[2]) {
+        match (self.buffer[0], self.buffer[1], self.buffer[2]) {
             (-1, 0, s) => {
-                score = s;
+                self.score = s;
             }
             (x, y, v) => {
-                screen.insert((x, y), v);
+                self.screen.insert(Point::new(x, y), v);
                 /*
                 0 is an empty tile. No game object appears in this tile.
                 1 is a wall tile. Walls are indestructible barriers.
@@ -71,17 +71,41 @@ pub fn star_two(input: impl BufRead) -> usize {
                 */
                 match v {
                     3 => {
-                        paddle_x = x;
+                        self.paddle_x = x;
                     }
                     4 => {
-                        ball_x = x;
+                        self.ball_x = x;
                     }
                     _ => {}
                 }
             }
         }
+        self.buffer.clear();
     }
-    score as usize
+}
+
+fn play(input: impl BufRead, mut arcade: Arcade) -> Result<Arcade, SolveError> {
+    let mut codes: Vec<isize> = parse::intcode(input)?;
+    // Memory address 0 represents the number of quarters that have been inserted; set it to 2 to play for free.
+    codes[0] = 2;
+    let mut computer = IntCode::new(codes, vec![]);
+    computer.run_controller(&mut arcade)?;
+    Ok(arcade)
+}
+
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let arcade = play(input, Arcade::default())?;
+    Ok(arcade.score as usize)
+}
+
+/// The game played through, one frame per move of the joystick.
+pub fn animate(input: impl BufRead) -> Result<Animation, SolveError> {
+    let arcade = Arcade {
+        frames: Some(Animation::new()),
+        ..Arcade::default()
+    };
+    let mut arcade = play(input, arcade)?;
+    Ok(arcade.frames.take().unwrap_or_default())
 }
 
 #[cfg(test)]
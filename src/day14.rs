@@ -6,24 +6,29 @@ use std::{
 
 use nom::{
     bytes::complete::tag,
-    character::complete::{alpha1, char, digit1, newline},
+    character::complete::{alpha1, char, digit1},
+    combinator::map_res,
     multi::{many0, separated_list1},
     sequence::{delimited, preceded, separated_pair},
     IResult, Parser,
 };
 
+use crate::{
+    parse::{self, LineError, ParseError},
+    solution::SolveError,
+};
+
 type Statement<'a> = (Vec<(&'a str, usize)>, (&'a str, usize));
 type Equations<'a> = HashMap<&'a str, (usize, &'a Vec<(&'a str, usize)>)>;
 
 fn parse_section(s: &str) -> IResult<&str, (&str, usize)> {
     let (input, (multiplier, symbol)) = delimited(
         many0(char(' ')),
-        separated_pair(digit1, tag(" "), alpha1),
+        separated_pair(map_res(digit1, str::parse::<usize>), tag(" "), alpha1),
         many0(char(' ')),
     )
     .parse(s)?;
-    let m = multiplier.parse::<usize>().unwrap();
-    Ok((input, (symbol, m)))
+    Ok((input, (symbol, multiplier)))
 }
 
 fn parse_statement(s: &str) -> IResult<&str, Vec<(&str, usize)>> {
@@ -33,22 +38,22 @@ fn parse_statement(s: &str) -> IResult<&str, Vec<(&str, usize)>> {
 }
 
 fn parse_line(s: &str) -> IResult<&str, Statement<'_>> {
-    let (input, (left, right)) =
-        separated_pair(parse_statement, tag("=>"), parse_statement).parse(s)?;
-    assert_eq!(right.len(), 1);
-    Ok((input, (left, right[0])))
+    separated_pair(parse_statement, tag("=>"), parse_section).parse(s)
 }
 
-fn parse_statements(s: &str) -> (Vec<&str>, Vec<Statement<'_>>) {
-    let (input, statements) = separated_list1(newline, parse_line).parse(s).unwrap();
-    assert_eq!(input.len(), 0);
+fn parse_statements(s: &str) -> Result<(Vec<&str>, Vec<Statement<'_>>), ParseError> {
+    let statements = parse::records(s, |line| match parse_line(line) {
+        Ok(("", statement)) => Ok(statement),
+        Ok((rest, _)) => Err(LineError::at(line, rest, "expected the end of the line")),
+        Err(e) => Err(LineError::nom(line, e, "N CHEMICAL, ... => N CHEMICAL")),
+    })?;
     // get all possible symbols
     let symbols: HashSet<_> = statements
         .iter()
         .flat_map(|(left, right)| left.iter().chain(once(right)).map(|s| s.0))
         .collect();
     let symbols: Vec<&str> = symbols.into_iter().collect();
-    (symbols, statements)
+    Ok((symbols, statements))
 }
 
 fn react<'a>(
@@ -56,16 +61,18 @@ fn react<'a>(
     current_state: &mut HashMap<&'a str, usize>,
     chemical: &'a str,
     amount: usize,
-) -> usize {
+) -> Result<usize, SolveError> {
     if chemical == "ORE" {
-        return amount;
+        return Ok(amount);
     }
     // println!("Trying to produce {} of {}", amount, chemical);
-    let (output_count, inputs) = mapping.get(chemical).unwrap();
+    let (output_count, inputs) = mapping
+        .get(chemical)
+        .ok_or_else(|| SolveError::no_solution(format!("no reaction produces {}", chemical)))?;
     let current_amount = *current_state.get(chemical).unwrap_or(&0);
     let amount_needed = if current_amount >= amount {
         current_state.insert(chemical, current_amount - amount);
-        return 0;
+        return Ok(0);
     } else {
         current_state.insert(chemical, 0);
         amount - current_amount
@@ -75,16 +82,15 @@ fn react<'a>(
     let ore_used = inputs
         .iter()
         .map(|(x, a)| react(mapping, current_state, x, *a * reacts_needed))
-        .sum::<usize>();
+        .sum::<Result<usize, _>>()?;
     *current_state.entry(chemical).or_insert(0) += output_count * reacts_needed - amount_needed;
     // println!("{}: {} => {} ({}, {}) {:?}", chemical, &ore_used, amount, reacts_needed, output_count, current_state);
-    ore_used
+    Ok(ore_used)
 }
 
-pub fn star_one(mut input: impl BufRead) -> usize {
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let (_symbols, statements) = parse_statements(&buf);
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let text = parse::text(input)?;
+    let (_symbols, statements) = parse_statements(&text)?;
     let mapping = statements
         .iter()
         .map(|(left, right)| (right.0, (right.1, left)))
@@ -93,10 +99,9 @@ pub fn star_one(mut input: impl BufRead) -> usize {
     react(&mapping, &mut current_state, "FUEL", 1)
 }
 
-pub fn star_two(mut input: impl BufRead) -> usize {
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let (_symbols, statements) = parse_statements(&buf);
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let text = parse::text(input)?;
+    let (_symbols, statements) = parse_statements(&text)?;
     let mapping = statements
         .iter()
         .map(|(left, right)| (right.0, (right.1, left)))
@@ -110,7 +115,7 @@ pub fn star_two(mut input: impl BufRead) -> usize {
     while lower < upper {
         let fuel = lower + (upper - lower) / 2;
         let mut current_state = HashMap::new();
-        let ores = react(&mapping, &mut current_state, "FUEL", fuel);
+        let ores = react(&mapping, &mut current_state, "FUEL", fuel)?;
         if ores <= target {
             max_fuel = max_fuel.max(fuel);
         }
@@ -121,7 +126,7 @@ pub fn star_two(mut input: impl BufRead) -> usize {
         }
     }
     // dbg!(symbols);
-    max_fuel
+    Ok(max_fuel)
 }
 
 #[cfg(test)]
@@ -192,7 +197,7 @@ mod tests {
 
     #[test]
     fn test_parsing() {
-        let (_symbols, statements) = parse_statements(INPUT1);
+        let (_symbols, statements) = parse_statements(INPUT1).unwrap();
         assert_eq!(
             statements,
             vec![
@@ -204,19 +209,25 @@ mod tests {
                 (vec![("A", 7), ("E", 1)], ("FUEL", 1))
             ]
         );
+        assert_eq!(
+            parse_statements("10 ORE => 10 A\n7 A, 1 B => C")
+                .unwrap_err()
+                .to_string(),
+            "line 2, column 13: expected N CHEMICAL, ... => N CHEMICAL"
+        );
     }
 
     #[test]
     fn test_star_one() {
-        assert_eq!(star_one(Cursor::new(INPUT1)), 31);
-        assert_eq!(star_one(Cursor::new(INPUT2)), 165);
-        assert_eq!(star_one(Cursor::new(INPUT3)), 13312);
+        assert_eq!(star_one(Cursor::new(INPUT1)).unwrap(), 31);
+        assert_eq!(star_one(Cursor::new(INPUT2)).unwrap(), 165);
+        assert_eq!(star_one(Cursor::new(INPUT3)).unwrap(), 13312);
     }
 
     #[test]
     fn test_star_two() {
-        assert_eq!(star_two(Cursor::new(INPUT3)), 82892753);
-        assert_eq!(star_two(Cursor::new(INPUT4)), 5586022);
-        assert_eq!(star_two(Cursor::new(INPUT5)), 460664);
+        assert_eq!(star_two(Cursor::new(INPUT3)).unwrap(), 82892753);
+        assert_eq!(star_two(Cursor::new(INPUT4)).unwrap(), 5586022);
+        assert_eq!(star_two(Cursor::new(INPUT5)).unwrap(), 460664);
     }
 }
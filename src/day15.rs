@@ -1,224 +1,107 @@
-use std::{
-    collections::{HashMap, HashSet, VecDeque},
-    io::BufRead,
-};
+use std::{collections::VecDeque, io::BufRead};
 
-use crate::IntCode;
+use crate::{
+    grid::{Point, SparseGrid},
+    intcode::IntCode,
+    parse, search,
+    solution::SolveError,
+};
 
 #[derive(Debug)]
 enum Space {
-    Unknown,
     Nothing,
     Wall,
     Oxygen,
 }
 
-type Position = (isize, isize);
-
 //north (1), south (2), west (3), and east (4)
-fn get_next_direction(
-    map: &HashMap<Position, Space>,
-    position: &Position,
-) -> Option<(isize, Position)> {
-    if let Space::Unknown = map
-        .get(&(position.0, position.1 - 1))
-        .unwrap_or(&Space::Unknown)
-    {
-        return Some((1, (position.0, position.1 - 1)));
-    };
-    if let Space::Unknown = map
-        .get(&(position.0, position.1 + 1))
-        .unwrap_or(&Space::Unknown)
-    {
-        return Some((2, (position.0, position.1 + 1)));
-    };
-    if let Space::Unknown = map
-        .get(&(position.0 - 1, position.1))
-        .unwrap_or(&Space::Unknown)
-    {
-        return Some((3, (position.0 - 1, position.1)));
-    };
-    if let Space::Unknown = map
-        .get(&(position.0 + 1, position.1))
-        .unwrap_or(&Space::Unknown)
-    {
-        return Some((4, (position.0 + 1, position.1)));
-    };
-    None
-}
-
-fn get_back(current_position: Position, proposed_position: Position) -> isize {
-    match (
-        current_position.0 - proposed_position.0,
-        current_position.1 - proposed_position.1,
-    ) {
-        (0, 1) => 1,
-        (0, -1) => 2,
-        (1, 0) => 3,
-        (-1, 0) => 4,
-        _ => panic!(),
-    }
-}
+const DIRECTIONS: [(isize, Point); 4] = [
+    (1, Point::UP),
+    (2, Point::DOWN),
+    (3, Point::LEFT),
+    (4, Point::RIGHT),
+];
 
 /*
-Explore the whole map and create a HashMap of the environment.
+Explore the whole map with a breadth first search over droid states, forking the computer at
+every step instead of walking the droid back. Returns the map and the oxygen system position.
  */
-fn get_map(mut computer: IntCode) -> (HashMap<Position, Space>, Position) {
-    let mut path = vec![(0, 0)];
-    let mut map = HashMap::new();
-    map.insert((0, 0), Space::Nothing);
-    let mut position = (0, 0);
+fn get_map(computer: IntCode) -> Result<(SparseGrid<Space>, Point), SolveError> {
+    let mut map = SparseGrid::new();
     let mut oxygen = None;
-    loop {
-        if let Some((proposed_dir, new_position)) = get_next_direction(&map, &position) {
-            let input = vec![proposed_dir];
-            computer.set_input(input);
-            computer.run(1);
-            let output = computer.take_output();
-            position = match output[0] {
-                // Wall
-                0 => {
+    map.insert(Point::ORIGIN, Space::Nothing);
+    let mut queue = VecDeque::new();
+    queue.push_back((Point::ORIGIN, computer));
+
+    while let Some((position, computer)) = queue.pop_front() {
+        for (direction, step) in DIRECTIONS {
+            let new_position = position + step;
+            if map.contains(new_position) {
+                continue;
+            }
+            let mut droid = computer.fork();
+            droid.push_input(direction);
+            droid.run(1)?;
+            /*
+            0: The repair droid hit a wall. Its position has not changed.
+            1: The repair droid has moved one step in the requested direction.
+            2: The repair droid has moved one step in the requested direction; its new position is the location of the oxygen system.
+            */
+            match droid.take_output()[..] {
+                [0] => {
                     map.insert(new_position, Space::Wall);
-                    position
                 }
-                // Moved
-                1 => {
+                [1] => {
                     map.insert(new_position, Space::Nothing);
-                    path.push(new_position);
-                    new_position
+                    queue.push_back((new_position, droid));
                 }
-                // Oxygen
-                2 => {
+                [2] => {
                     map.insert(new_position, Space::Oxygen);
-                    path.push(new_position);
-                    oxygen.replace(new_position);
-                    new_position
+                    oxygen.get_or_insert(new_position);
+                    queue.push_back((new_position, droid));
+                }
+                ref output => {
+                    return Err(SolveError::no_solution(format!(
+                        "unexpected droid status {:?}",
+                        output
+                    )))
                 }
-                _ => panic!(),
             }
-        } else if path.len() > 1 {
-            // Backtrack
-            let _current_position = path.pop();
-            let proposed_position = *path.last().unwrap();
-            let proposed_dir = get_back(position, proposed_position);
-            let input = vec![proposed_dir];
-            computer.set_input(input);
-            computer.run(1);
-            let output = computer.take_output();
-            assert_eq!(output, vec![1]);
-            position = proposed_position;
-        } else {
-            break;
         }
     }
-    (map, oxygen.unwrap())
+    let oxygen =
+        oxygen.ok_or_else(|| SolveError::no_solution("the droid never finds the oxygen system"))?;
+    Ok((map, oxygen))
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-    let computer = IntCode::new(codes, vec![]);
-
-    let (map, oxygen) = get_map(computer);
-    /*
-    0: The repair droid hit a wall. Its position has not changed.
-    1: The repair droid has moved one step in the requested direction.
-    2: The repair droid has moved one step in the requested direction; its new position is the location of the oxygen system.
-    */
+/// The open spaces next to `position`.
+fn get_open(map: &SparseGrid<Space>, position: Point) -> impl Iterator<Item = Point> + '_ {
+    position
+        .neighbours4()
+        .filter(|&p| matches!(map.get(p), Some(Space::Nothing | Space::Oxygen)))
+}
 
-    // get shortest path back
-    let mut visited = HashSet::new();
-    let mut stack = VecDeque::new();
-    stack.push_back((oxygen, 0));
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    let computer = IntCode::new(codes, vec![]);
 
-    // Start from the oxygen supply and do a breadth first search of the map to get back to the start (0,0).
-    // TODO: Change to BFS.
-    while let Some((position, steps)) = stack.pop_front() {
-        if !visited.contains(&position) {
-            visited.insert(position);
-            if position == (0, 0) {
-                return steps;
-            }
-            match map.get(&position).unwrap() {
-                Space::Unknown => todo!(),
-                Space::Nothing | Space::Oxygen => {
-                    stack.push_back(((position.0, position.1 + 1), steps + 1));
-                    stack.push_back(((position.0, position.1 - 1), steps + 1));
-                    stack.push_back(((position.0 + 1, position.1), steps + 1));
-                    stack.push_back(((position.0 - 1, position.1), steps + 1));
-                }
-                Space::Wall => {}
-            }
-        }
-    }
-    // println!("{:?}", map);
-    unreachable!()
+    let (map, oxygen) = get_map(computer)?;
+    let path = search::bfs([Point::ORIGIN], |&p| get_open(&map, p), |&p| p == oxygen)
+        .ok_or_else(|| SolveError::no_solution("the oxygen system can't be reached"))?;
+    Ok(path.cost)
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let computer = IntCode::new(codes, vec![]);
 
-    let (map, oxygen) = get_map(computer);
-
-    // Flood fill from oxygen.
-    let mut stack = Vec::new();
-    let mut flood_map = HashMap::new();
-    flood_map.insert(oxygen, 0);
-    stack.push((oxygen.0, oxygen.1 + 1));
-    stack.push((oxygen.0, oxygen.1 - 1));
-    stack.push((oxygen.0 + 1, oxygen.1));
-    stack.push((oxygen.0 - 1, oxygen.1));
-    let mut max_steps = 0;
+    let (map, oxygen) = get_map(computer)?;
 
-    while let Some(p) = stack.pop() {
-        let lowest_neighbour = *flood_map
-            .get(&(p.0, p.1 + 1))
-            .unwrap_or(&usize::MAX)
-            .min(flood_map.get(&(p.0, p.1 - 1)).unwrap_or(&usize::MAX))
-            .min(flood_map.get(&(p.0 + 1, p.1)).unwrap_or(&usize::MAX))
-            .min(flood_map.get(&(p.0 - 1, p.1)).unwrap_or(&usize::MAX));
-        let f = flood_map.entry(p);
-        match map.get(&p).unwrap_or(&Space::Unknown) {
-            Space::Unknown => todo!(),
-            Space::Nothing => {
-                match f {
-                    std::collections::hash_map::Entry::Occupied(x) => {
-                        assert!(x.get() <= &(lowest_neighbour + 1));
-                    }
-                    std::collections::hash_map::Entry::Vacant(x) => {
-                        x.insert(lowest_neighbour + 1);
-                        max_steps = max_steps.max(lowest_neighbour + 1);
-                        stack.push((p.0, p.1 + 1));
-                        stack.push((p.0, p.1 - 1));
-                        stack.push((p.0 + 1, p.1));
-                        stack.push((p.0 - 1, p.1));
-                    }
-                };
-            }
-            Space::Wall => {}
-            Space::Oxygen => {}
-        };
-    }
+    // Oxygen spreads one step a minute, so the last space to fill is the furthest from it.
+    let distances = search::bfs_all([oxygen], |&p| get_open(&map, p));
+    let max_steps = distances.iter().map(|(_, steps)| steps).max().unwrap_or(0);
 
-    max_steps
+    Ok(max_steps)
 }
 
 #[cfg(test)]
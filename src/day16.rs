@@ -1,6 +1,11 @@
 use rayon::prelude::*;
 use std::{io::BufRead, iter::repeat_n};
 
+use crate::{
+    parse::{self, ParseError},
+    solution::SolveError,
+};
+
 fn run(codes: Vec<isize>, loops: usize) -> Vec<isize> {
     let l = codes.len();
     (0..loops).fold(codes, |phase, _i| {
@@ -31,30 +36,35 @@ fn convert_number(output: &[isize]) -> usize {
     output.iter().fold(0, |total, &x| total * 10 + x) as usize
 }
 
-pub fn star_one(mut input: impl BufRead) -> usize {
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let codes: Vec<isize> = buf
-        .chars()
-        .map(|b| b.to_digit(10).unwrap() as isize)
-        .collect();
+/// Reads the signal, which must be at least as long as the eight digit message.
+fn read_signal(input: impl BufRead) -> Result<Vec<isize>, SolveError> {
+    let codes = parse::digits(input)?;
+    if codes.len() < 8 {
+        return Err(ParseError::new(1, codes.len() + 1, "expected at least 8 digits").into());
+    }
+    Ok(codes.into_iter().map(isize::from).collect())
+}
+
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes = read_signal(input)?;
 
     let output = run(codes, 100);
-    println!("{:?}", &output[..8]);
-    convert_number(&output[..8])
+    Ok(convert_number(&output[..8]))
 }
 
-pub fn star_two(mut input: impl BufRead) -> usize {
-    let mut buf = String::new();
-    let _input_file = input.read_to_string(&mut buf);
-    let codes: Vec<isize> = buf
-        .chars()
-        .map(|b| b.to_digit(10).unwrap() as isize)
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes = read_signal(input)?;
     let new_length = 10000 * codes.len();
     let input_phase: Vec<isize> = codes.iter().cycle().take(new_length).copied().collect();
 
     let offset = convert_number(&input_phase[..7]);
+    // Only the second half of the signal is a running sum of the digits after it.
+    if offset < new_length / 2 || offset + 8 > new_length {
+        return Err(SolveError::no_solution(format!(
+            "message offset {} is not in the second half of the signal",
+            offset
+        )));
+    }
     let mut data = input_phase[offset..].to_vec();
     for _i in 0..100 {
         data = data
@@ -67,7 +77,7 @@ pub fn star_two(mut input: impl BufRead) -> usize {
             .collect();
         data.reverse();
     }
-    convert_number(&data[..8])
+    Ok(convert_number(&data[..8]))
 }
 
 #[cfg(test)]
@@ -99,15 +109,15 @@ mod tests {
     #[test]
     fn test_star_one() {
         assert_eq!(
-            star_one(Cursor::new(b"80871224585914546619083218645595")),
+            star_one(Cursor::new(b"80871224585914546619083218645595")).unwrap(),
             24176176
         );
         assert_eq!(
-            star_one(Cursor::new("19617804207202209144916044189917")),
+            star_one(Cursor::new("19617804207202209144916044189917")).unwrap(),
             73745418
         );
         assert_eq!(
-            star_one(Cursor::new("69317163492948606335995924319873")),
+            star_one(Cursor::new("69317163492948606335995924319873")).unwrap(),
             52432133
         );
     }
@@ -115,15 +125,15 @@ mod tests {
     #[test]
     fn test_star_two() {
         assert_eq!(
-            star_two(Cursor::new(b"03036732577212944063491565474664")),
+            star_two(Cursor::new(b"03036732577212944063491565474664")).unwrap(),
             84462026
         );
         assert_eq!(
-            star_two(Cursor::new("02935109699940807407585447034323")),
+            star_two(Cursor::new("02935109699940807407585447034323")).unwrap(),
             78725270
         );
         assert_eq!(
-            star_two(Cursor::new("03081770884921959731165446850517")),
+            star_two(Cursor::new("03081770884921959731165446850517")).unwrap(),
             53553731
         );
     }
@@ -1,74 +1,63 @@
-use std::{collections::HashSet, io::BufRead};
+use std::io::BufRead;
 
-use crate::IntCode;
+use crate::{
+    grid::{Point, SparseGrid},
+    intcode::IntCode,
+    parse,
+    solution::SolveError,
+};
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-    (0..50)
-        .map(|y| {
-            (0..50)
-                .map(|x| get_point(codes.clone(), x, y))
-                .filter(|&x| x == 1)
-                .count()
-        })
-        .sum()
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    let drone = drone(codes);
+    let mut affected = 0;
+    for y in 0..50 {
+        for x in 0..50 {
+            if get_point(&drone, x, y)? == 1 {
+                affected += 1;
+            }
+        }
+    }
+    Ok(affected)
 }
 
-fn get_point(program: Vec<isize>, x: usize, y: usize) -> isize {
-    let mut computer = IntCode::new(program, vec![x as isize, y as isize]);
-    computer.run(1);
+/// The drone program, decoded once so that every point can fork it instead of starting over.
+fn drone(program: Vec<isize>) -> IntCode {
+    let mut computer = IntCode::new(program, vec![]);
+    computer.predecode();
+    computer
+}
+
+fn get_point(drone: &IntCode, x: usize, y: usize) -> Result<isize, SolveError> {
+    let mut computer = drone.fork();
+    computer.set_input(vec![x as isize, y as isize]);
+    computer.run(1)?;
     let output = computer.take_output();
     // assert_eq!(output.len(), 1);
-    output[0]
+    output.first().copied().ok_or_else(|| {
+        SolveError::no_solution(format!("the drone sent no reading for {},{}", x, y))
+    })
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
-    let sample_x = 40;
-    let upper = (0..)
-        .map(|y| {
-            if get_point(codes.clone(), sample_x, y) == 1 {
-                '#'
-            } else {
-                '.'
-            }
-        })
-        .enumerate()
-        .find(|&(_, x)| x == '#')
-        .map(|x| x.0)
-        .unwrap();
+/// The first row from `from` on where column `x` is pulled by the beam, or isn't.
+fn find_row(drone: &IntCode, x: usize, from: usize, pulled: bool) -> Result<usize, SolveError> {
+    for y in from..from + 1000 {
+        if (get_point(drone, x, y)? == 1) == pulled {
+            return Ok(y);
+        }
+    }
+    Err(SolveError::no_solution(format!(
+        "the beam edge never crosses column {}",
+        x
+    )))
+}
 
-    let lower = upper
-        + (upper..)
-            .map(|y| {
-                if get_point(codes.clone(), sample_x, y) == 1 {
-                    '#'
-                } else {
-                    '.'
-                }
-            })
-            .enumerate()
-            .find(|&(_, x)| x == '.')
-            .map(|x| x.0)
-            .unwrap();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
+    let drone = drone(codes);
+    let sample_x = 40;
+    let upper = find_row(&drone, sample_x, 0, true)?;
+    let lower = find_row(&drone, sample_x, upper, false)?;
 
     let upper_angle = sample_x as f64 / (upper - 2) as f64;
     let lower_angle = sample_x as f64 / (lower + 2) as f64;
@@ -80,27 +69,34 @@ pub fn star_two(input: impl BufRead) -> usize {
     let min_height = 1000;
     let min_width = 800;
     // println!("{}", lower_angle * 100f64 / (upper_angle - lower_angle));
-    let map: HashSet<(usize, usize)> = (min_height..=max_height)
-        .flat_map(|y| {
-            let lower_x = f64::floor(lower_angle * (y as f64)) as usize;
-            let upper_x = f64::ceil(upper_angle * (y as f64)) as usize;
-            // println!("{} - {}", lower_x, upper_x);
-            (lower_x..=upper_x)
-                .map(move |x| (x, y))
-                .filter(|pos| get_point(codes.clone(), pos.0, pos.1) == 1)
-        })
-        .collect();
+    let mut map: SparseGrid<()> = SparseGrid::new();
+    for y in min_height..=max_height {
+        let lower_x = f64::floor(lower_angle * (y as f64)) as usize;
+        let upper_x = f64::ceil(upper_angle * (y as f64)) as usize;
+        // println!("{} - {}", lower_x, upper_x);
+        for x in lower_x..=upper_x {
+            if get_point(&drone, x, y)? == 1 {
+                map.insert(Point::new(x as isize, y as isize), ());
+            }
+        }
+    }
 
     // println!("Starting checks");
-    for y in min_height..max_height {
-        for x in min_width..max_width {
-            if map.contains(&(x, y)) && map.contains(&(x + 99, y)) && map.contains(&(x, y + 99)) {
-                return x * 10000 + y;
+    for y in min_height as isize..max_height as isize {
+        for x in min_width as isize..max_width as isize {
+            let corner = Point::new(x, y);
+            if map.contains(corner)
+                && map.contains(corner + Point::new(99, 0))
+                && map.contains(corner + Point::new(0, 99))
+            {
+                return Ok(x as usize * 10000 + y as usize);
             }
         }
     }
 
-    unreachable!()
+    Err(SolveError::no_solution(
+        "the ship doesn't fit in the beam within the search area",
+    ))
 }
 
 #[cfg(test)]
@@ -1,148 +1,109 @@
-use std::{
-    cmp::Reverse,
-    collections::{HashMap, HashSet, VecDeque},
-    io::BufRead,
+use std::{collections::HashMap, io::BufRead};
+
+use crate::{
+    grid::{Grid, Point},
+    parse, search,
+    solution::SolveError,
 };
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 enum Teleport {
     Inner(char, char),
     Outer(char, char),
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
 enum Tile {
     Wall,
     Empty,
     Teleport(Teleport),
 }
 
-fn get_teleporter(map: &[Vec<char>], x: usize, y: usize) -> Option<(char, char)> {
-    if map[y - 2][x].is_ascii_alphabetic() && map[y - 1][x].is_ascii_alphabetic() {
-        return Some((map[y - 2][x], map[y - 1][x]));
-    }
-
-    if map[y + 1][x].is_ascii_alphabetic() && map[y + 2][x].is_ascii_alphabetic() {
-        return Some((map[y + 1][x], map[y + 2][x]));
-    }
-
-    if map[y][x - 2].is_ascii_alphabetic() && map[y][x - 1].is_ascii_alphabetic() {
-        return Some((map[y][x - 2], map[y][x - 1]));
-    }
-
-    if map[y][x + 1].is_ascii_alphabetic() && map[y][x + 2].is_ascii_alphabetic() {
-        return Some((map[y][x + 1], map[y][x + 2]));
-    }
-
-    None
+fn get_teleporter(map: &Grid<char>, position: Point) -> Option<(char, char)> {
+    let letter = |p: Point| map.get(p).copied().filter(char::is_ascii_alphabetic);
+    Point::ADJACENT.into_iter().find_map(|step| {
+        let near = letter(position + step)?;
+        let far = letter(position + step + step)?;
+        // Labels read top to bottom and left to right.
+        if step.x + step.y < 0 {
+            Some((far, near))
+        } else {
+            Some((near, far))
+        }
+    })
 }
 
 fn get_map(
     input: impl BufRead,
-    map_teleport: &impl Fn((char, char), (usize, usize), (usize, usize)) -> Tile,
-) -> Vec<Vec<Tile>> {
-    let raw_map: Vec<Vec<char>> = input
-        .lines()
-        .map(|v| {
-            // println!("{}", &v);
-            v.unwrap().chars().collect::<Vec<char>>()
-        })
-        .collect();
-    let raw_height = raw_map.len();
-    let map_height = raw_height - 4;
-    raw_map
-        .iter()
-        .enumerate()
-        .skip(2)
-        .take(raw_height - 4)
-        .map(|(y, row)| {
-            let raw_width = row.len();
-            let map_width = raw_width - 4;
+    map_teleport: &impl Fn((char, char), Point, (usize, usize)) -> Tile,
+) -> Result<Grid<Tile>, SolveError> {
+    let raw_map = Grid::from_rows(
+        parse::grid(input, |c| {
+            matches!(c, '.' | '#' | ' ' | 'A'..='Z' | 'a'..='z').then_some(c)
+        })?,
+        ' ',
+    );
+    if raw_map.width() < 5 || raw_map.height() < 5 {
+        return Err(SolveError::no_solution(
+            "the maze is too small to have a border of portal labels",
+        ));
+    }
+    // The maze proper sits inside a two tile border of portal labels.
+    let size = (raw_map.width() - 4, raw_map.height() - 4);
+    let border = Point::new(2, 2);
+    let mut map = Grid::new(size.0, size.1, Tile::Wall);
+    for position in map.points() {
+        let raw_position = position + border;
+        // Walls, labels and the space around the maze stay walls.
+        if raw_map[raw_position] == '.' {
+            // Check if teleporter
+            map[position] = get_teleporter(&raw_map, raw_position)
+                .map(|(a, b)| map_teleport((a, b), position, size))
+                .unwrap_or(Tile::Empty);
+        }
+    }
+    Ok(map)
+}
 
-            row.iter()
-                .enumerate()
-                .skip(2)
-                .take(raw_width - 4)
-                .map(|(x, c)| {
-                    match *c {
-                        '.' => {
-                            // Check if teleporter
-                            get_teleporter(&raw_map, x, y)
-                                .map(|(a, b)| {
-                                    map_teleport((a, b), (x - 2, y - 2), (map_width, map_height))
-                                })
-                                .unwrap_or(Tile::Empty)
-                        }
-                        '#' => Tile::Wall,
-                        'A'..='Z' | 'a'..='z' => Tile::Wall,
-                        ' ' => Tile::Wall,
-                        c => panic!("{:?} at ({}, {})", c, x, y),
-                    }
-                })
-                .collect()
-        })
-        .collect()
+fn teleporter_positions(map: &Grid<Tile>) -> HashMap<&Tile, Vec<Point>> {
+    let mut teleporter_positions = HashMap::new();
+    for (position, c) in map.iter() {
+        if let Tile::Teleport(_teleport) = c {
+            teleporter_positions
+                .entry(c)
+                .or_insert_with(Vec::new)
+                .push(position);
+        }
+    }
+    teleporter_positions
 }
 
-fn get_graph(map: &[Vec<Tile>]) -> HashMap<&Teleport, HashMap<&Teleport, usize>> {
+fn get_graph(map: &Grid<Tile>) -> HashMap<&Teleport, HashMap<&Teleport, usize>> {
     let mut teleporters = Vec::new();
 
-    let deltas = vec![(-1, 0), (1, 0), (0, -1), (0, 1)];
-    for (y, row) in map.iter().enumerate() {
-        for (x, c) in row.iter().enumerate() {
-            // match c {
-            //     Tile::Wall => print!("#"),
-            //     Tile::Empty => print!("."),
-            //     Tile::Teleport(a, _) => print!("{}", a),
-            // }
-            if let Tile::Teleport(teleport) = c {
-                teleporters.push((teleport, (x, y)));
-            }
+    for (position, c) in map.iter() {
+        if let Tile::Teleport(teleport) = c {
+            teleporters.push((teleport, position));
         }
-        // println!()
     }
 
     let mut graph = HashMap::new();
 
-    for (from_tile, start) in &teleporters {
-        let mut costs = HashMap::new();
-
-        let mut stack = VecDeque::new();
-        stack.push_back((*start, 0));
-
-        let mut visited = HashSet::new();
-
-        while let Some(((x, y), steps)) = stack.pop_front() {
-            if !visited.contains(&(x, y)) {
-                visited.insert((x, y));
-                for (d_x, d_y) in &deltas {
-                    // println!("Looking at ({}, {})", x, y);
-                    let n_y = (y as isize + d_y) as usize;
-                    let n_x = (x as isize + d_x) as usize;
-                    match map.get(n_y).and_then(|row| row.get(n_x)) {
-                        None => {}
-                        Some(tile) => {
-                            match tile {
-                                Tile::Wall => {}
-                                Tile::Empty => {
-                                    // add surrounding tiles
-                                    stack.push_back(((n_x, n_y), steps + 1));
-                                }
-
-                                Tile::Teleport(to_tile) => {
-                                    // This should be the first time we have seend it so it should be the quickest path.
-                                    if !visited.contains(&(n_x, n_y))
-                                        && costs.get(to_tile).unwrap_or(&usize::MAX) > &steps
-                                    {
-                                        costs.insert(to_tile, steps + 1);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
+    for &(from_tile, start) in &teleporters {
+        // Walk the empty tiles, stopping at any other teleporter.
+        let distances = search::bfs_all([start], |&position| {
+            let passable = position == start || map[position] == Tile::Empty;
+            map.neighbours4(position)
+                .filter(move |&next| passable && map[next] != Tile::Wall)
+        });
+        let mut costs: HashMap<&Teleport, usize> = distances
+            .iter()
+            .filter(|&(&position, _)| position != start)
+            .filter_map(|(&position, steps)| match &map[position] {
+                Tile::Teleport(to_tile) => Some((to_tile, steps)),
+                _ => None,
+            })
+            .collect();
         let to_tile = match from_tile {
             Teleport::Inner(a, b) => teleporters.iter().find(|t| t.0 == &Teleport::Outer(*a, *b)),
             Teleport::Outer(a, b) => teleporters.iter().find(|t| t.0 == &Teleport::Inner(*a, *b)),
@@ -151,134 +112,71 @@ fn get_graph(map: &[Vec<Tile>]) -> HashMap<&Teleport, HashMap<&Teleport, usize>>
             costs.insert(to_tile.0, 1);
         }
 
-        graph.insert(*from_tile, costs);
+        graph.insert(from_tile, costs);
     }
     graph
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
     let map_teleport = |(a, b), _, _| Tile::Teleport(Teleport::Outer(a, b));
-    let map = get_map(input, &map_teleport);
-    let mut teleporter_positions = HashMap::new();
-    for (y, row) in map.iter().enumerate() {
-        for (x, c) in row.iter().enumerate() {
-            // match c {
-            //     Tile::Wall => print!("#"),
-            //     Tile::Empty => print!("."),
-            //     Tile::Teleport(a, _) => print!("{}", a),
-            // }
-            if let Tile::Teleport(_teleport) = c {
-                teleporter_positions
-                    .entry(c)
-                    .or_insert_with(Vec::new)
-                    .push((x, y));
-            }
-        }
-        // println!()
-    }
-    // println!("{:?}", teleporter_positions);
+    let map = get_map(input, &map_teleport)?;
+    let teleporter_positions = teleporter_positions(&map);
     let start = teleporter_positions
         .get(&Tile::Teleport(Teleport::Outer('A', 'A')))
-        .unwrap()[0];
-
-    let mut stack = vec![
-        ((start.0.wrapping_sub(1), start.1), 1),
-        ((start.0 + 1, start.1), 1),
-        ((start.0, start.1.wrapping_sub(1)), 1),
-        ((start.0, start.1 + 1), 1),
-    ];
-
-    let mut costs = vec![vec![usize::MAX; map[0].len()]; map.len()];
-
-    costs[start.1][start.0] = 0;
-
-    while let Some(((x, y), steps)) = stack.pop() {
-        // println!("Looking at ({}, {})", x, y);
-        match map.get(y).and_then(|row| row.get(x)) {
-            None => {}
-            Some(Tile::Wall) => {}
-
-            Some(Tile::Empty) => {
-                if costs[y][x] > steps {
-                    costs[y][x] = steps;
-                    // add surrounding tiles
-                    stack.push(((x - 1, y), steps + 1));
-                    stack.push(((x + 1, y), steps + 1));
-
-                    stack.push(((x, y - 1), steps + 1));
-                    stack.push(((x, y + 1), steps + 1));
-                }
-            }
-            Some(Tile::Teleport(_teleport)) if costs[y][x] > steps => {
-                costs[y][x] = steps;
-                // add teleported position to stack
-                let new_positions = teleporter_positions.get(&map[y][x]).unwrap();
-                if new_positions.len() > 1 {
-                    assert_eq!(new_positions.len(), 2, "Panic'd at {} {}", x, y);
-                    let (new_x, new_y) = new_positions.iter().find(|&p| p != &(x, y)).unwrap();
-                    costs[*new_y][*new_x] = steps + 1;
-                    let x = *new_x;
-                    let y = *new_y;
-                    stack.push(((x.wrapping_sub(1), y), steps + 2));
-                    stack.push(((x + 1, y), steps + 2));
-
-                    stack.push(((x, y.wrapping_sub(1)), steps + 2));
-                    stack.push(((x, y + 1), steps + 2));
-                }
-            }
-            _ => {}
-        }
-    }
+        .ok_or_else(|| SolveError::no_solution("the maze has no entrance AA"))?[0];
     let end = teleporter_positions
         .get(&Tile::Teleport(Teleport::Outer('Z', 'Z')))
-        .unwrap()[0];
-    costs[end.1][end.0]
+        .ok_or_else(|| SolveError::no_solution("the maze has no exit ZZ"))?[0];
+    if let Some(ends) = teleporter_positions.values().find(|ends| ends.len() > 2) {
+        return Err(SolveError::no_solution(format!(
+            "portal at ({}, {}) has more than two ends",
+            ends[0].x, ends[0].y
+        )));
+    }
+
+    let path = search::bfs(
+        [start],
+        |&position| {
+            let mut next: Vec<Point> = map
+                .neighbours4(position)
+                .filter(|&p| map[p] != Tile::Wall)
+                .collect();
+            // Stepping through a teleporter takes one step to its other end.
+            if let Some(ends) = teleporter_positions.get(&map[position]) {
+                next.extend(ends.iter().filter(|&&p| p != position));
+            }
+            next
+        },
+        |&position| position == end,
+    );
+    path.map(|path| path.cost)
+        .ok_or_else(|| SolveError::no_solution("there is no path from AA to ZZ"))
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let map_teleport = |(a, b), (x, y), (width, height)| {
-        if x == 0 || y == 0 || x == width - 1 || y == height - 1 {
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let map_teleport = |(a, b), p: Point, (width, height): (usize, usize)| {
+        if p.x == 0 || p.y == 0 || p.x == width as isize - 1 || p.y == height as isize - 1 {
             Tile::Teleport(Teleport::Outer(a, b))
         } else {
             Tile::Teleport(Teleport::Inner(a, b))
         }
     };
-    let map = get_map(input, &map_teleport);
-    let mut teleporter_positions = HashMap::new();
-    for (y, row) in map.iter().enumerate() {
-        for (x, c) in row.iter().enumerate() {
-            // match c {
-            //     Tile::Wall => print!("#"),
-            //     Tile::Empty => print!("."),
-            //     Tile::Teleport(a, _) => print!("{}", a),
-            // }
-            if let Tile::Teleport(_teleport) = c {
-                teleporter_positions
-                    .entry(c)
-                    .or_insert_with(Vec::new)
-                    .push((x, y));
-            }
-        }
-        // println!()
-    }
+    let map = get_map(input, &map_teleport)?;
     let graph = get_graph(&map);
 
     let start = Teleport::Outer('A', 'A');
-
-    let mut costs = HashMap::new();
-
-    let mut stack = vec![(&start, 0, 0)];
-
-    while let Some((current_teleport, current_depth, current_cost)) = stack.pop() {
-        if current_teleport == &Teleport::Outer('Z', 'Z') && current_depth == 0 {
-            return current_cost;
-        }
-        let cost = *costs
-            .get(&(current_depth, current_teleport))
-            .unwrap_or(&usize::MAX);
-        if cost >= current_cost {
-            costs.insert((current_depth, current_teleport), current_cost);
-            for (next_teleport, &delta_cost) in graph.get(&current_teleport).unwrap() {
+    let end = Teleport::Outer('Z', 'Z');
+
+    // Search over (depth, teleporter) pairs: inner teleporters lead one level down and outer
+    // ones one level up, and the outermost level has no way up. A shortest path never needs to
+    // go deeper than there are teleporters, so stop there rather than search forever when ZZ
+    // can't be reached.
+    let max_depth = graph.len();
+    let path = search::dijkstra(
+        [(0, &start)],
+        |&(current_depth, current_teleport): &(usize, &Teleport)| {
+            let mut next_states = Vec::new();
+            for (next_teleport, &delta_cost) in graph.get(current_teleport).into_iter().flatten() {
                 if !(matches!(current_teleport, Teleport::Outer(_, _))
                     && current_depth == 0
                     && delta_cost == 1)
@@ -291,15 +189,17 @@ pub fn star_two(input: impl BufRead) -> usize {
                     } else {
                         current_depth
                     };
-                    let new_cost = current_cost + delta_cost;
-                    stack.push((next_teleport, next_depth, new_cost));
+                    if next_depth <= max_depth {
+                        next_states.push(((next_depth, *next_teleport), delta_cost));
+                    }
                 }
             }
-        }
-        stack.sort_by_cached_key(|x| Reverse(x.2));
-    }
-
-    unreachable!();
+            next_states
+        },
+        |&(depth, teleport)| depth == 0 && teleport == &end,
+    );
+    path.map(|path| path.cost)
+        .ok_or_else(|| SolveError::no_solution("there is no path from AA to ZZ"))
 }
 
 #[cfg(test)]
@@ -405,13 +305,20 @@ RE....#.#                           #......RF
 
     #[test]
     fn test_star_one() {
-        assert_eq!(star_one(Cursor::new(INPUT1)), 23);
-        assert_eq!(star_one(Cursor::new(INPUT2)), 58);
+        assert_eq!(star_one(Cursor::new(INPUT1)).unwrap(), 23);
+        assert_eq!(star_one(Cursor::new(INPUT2)).unwrap(), 58);
     }
 
     #[test]
     fn test_star_two() {
         // assert_eq!(star_two(Cursor::new(INPUT1)), 23);
-        assert_eq!(star_two(Cursor::new(INPUT3)), 396);
+        assert_eq!(star_two(Cursor::new(INPUT3)).unwrap(), 396);
+    }
+
+    #[test]
+    fn test_star_two_no_path() {
+        // Wall off ZZ, leaving the portals free to lead ever deeper.
+        let input = INPUT1.replace("FG..#########.....#", "FG..##########....#");
+        assert!(star_two(Cursor::new(input)).is_err());
     }
 }
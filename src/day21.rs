@@ -1,47 +1,26 @@
 use std::io::BufRead;
 
-use crate::IntCode;
+use crate::{intcode::ascii::AsciiIntCode, parse, solution::SolveError};
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let commands = "NOT A T
 NOT C J
 OR T J
 AND D J
 WALK
 ";
-    let input = commands.chars().map(|c| c as isize).collect();
-    let mut computer = IntCode::new(codes, input);
-    computer.run(0);
-    let output = computer.take_output();
-    // for &o in &output {
-    //     if o < 300 {
-    //         print!("{}", char::from_u32(o as u32).unwrap());
-    //     }
-    // }
-    *output.last().unwrap() as usize
+    let mut computer = AsciiIntCode::new(codes);
+    computer.send(commands);
+    computer.read_until_prompt()?;
+    let damage = computer
+        .value()
+        .ok_or_else(|| SolveError::no_solution("the springdroid fell into space"))?;
+    Ok(damage as usize)
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let codes: Vec<isize> = input
-        .split(b',')
-        .map(|v| {
-            // println!("{}", &v);
-            String::from_utf8(v.unwrap())
-                .unwrap()
-                .parse::<isize>()
-                .unwrap()
-        })
-        .collect();
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let codes: Vec<isize> = parse::intcode(input)?;
     let commands = "NOT C J 
 AND D J 
 AND H J
@@ -52,17 +31,13 @@ NOT A T
 OR T J
 RUN
 ";
-    let input = commands.chars().map(|c| c as isize).collect();
-    // println!("{:?}", input);
-    let mut computer = IntCode::new(codes, input);
-    computer.run(0);
-    let output = computer.take_output();
-    // for &o in &output {
-    //     if o < 300 {
-    //         print!("{}", char::from_u32(o as u32).unwrap());
-    //     }
-    // }
-    *output.last().unwrap() as usize
+    let mut computer = AsciiIntCode::new(codes);
+    computer.send(commands);
+    computer.read_until_prompt()?;
+    let damage = computer
+        .value()
+        .ok_or_else(|| SolveError::no_solution("the springdroid fell into space"))?;
+    Ok(damage as usize)
 }
 
 #[cfg(test)]
@@ -1,5 +1,10 @@
 use std::{io::BufRead, str::FromStr};
 
+use crate::{
+    parse::{self, LineError, ParseError},
+    solution::SolveError,
+};
+
 enum Instruction {
     NewStack,
     Cut(isize),
@@ -7,17 +12,22 @@ enum Instruction {
 }
 
 impl FromStr for Instruction {
-    type Err = String;
+    type Err = LineError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let parts: Vec<&str> = s.rsplitn(2, ' ').collect();
-        match parts[1] {
-            "deal into new" => Ok(Instruction::NewStack),
-            "cut" => Ok(Instruction::Cut(
-                parts[0].parse().unwrap_or_else(|_| panic!("{}", s)),
+        let (technique, n) = s.trim().rsplit_once(' ').unwrap_or(("", s));
+        match technique {
+            "deal into new" if n == "stack" => Ok(Instruction::NewStack),
+            "cut" => Ok(Instruction::Cut(parse::number(s, n)?)),
+            "deal with increment" => match parse::number(s, n)? {
+                increment if increment > 0 => Ok(Instruction::Increment(increment)),
+                _ => Err(LineError::at(s, n, "the increment must be positive")),
+            },
+            _ => Err(LineError::at(
+                s,
+                s.trim_start(),
+                format!("unknown technique {:?}", s.trim()),
             )),
-            "deal with increment" => Ok(Instruction::Increment(parts[0].parse().unwrap())),
-            x => panic!("{}: {}", s, x),
         }
     }
 }
@@ -48,21 +58,15 @@ fn star_one_stack(instructions: &[Instruction], mut stack: Vec<isize>) -> Vec<is
     stack
 }
 
-fn parse_instructions(input: impl BufRead) -> Vec<Instruction> {
-    input
-        .lines()
-        .map(|v| {
-            // println!("{}", &v);
-            Instruction::from_str(&v.unwrap()).unwrap()
-        })
-        .collect()
+fn parse_instructions(input: impl BufRead) -> Result<Vec<Instruction>, ParseError> {
+    parse::records(&parse::text(input)?, Instruction::from_str)
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let instructions = parse_instructions(input);
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let instructions = parse_instructions(input)?;
     let stack_size = 10007;
     let output = star_one_stack(&instructions, (0..stack_size).collect());
-    output.into_iter().position(|x| x == 2019).unwrap()
+    Ok(output.into_iter().position(|x| x == 2019).unwrap())
 }
 
 // adapted from https://github.com/simon-andrews/rust-modinverse/blob/master/src/lib.rs
@@ -84,37 +88,46 @@ pub fn modinverse(a: i128, m: i128) -> Option<i128> {
     }
 }
 
-fn get_multiplier(instructions: &[Instruction], stack_size: i128) -> i128 {
+fn inverse_increment(x: isize, stack_size: i128) -> Result<i128, SolveError> {
+    modinverse(x as i128, stack_size).ok_or_else(|| {
+        SolveError::no_solution(format!(
+            "dealing with increment {} can't be undone on {} cards",
+            x, stack_size
+        ))
+    })
+}
+
+fn get_multiplier(instructions: &[Instruction], stack_size: i128) -> Result<i128, SolveError> {
     instructions
         .iter()
         .rev()
-        .fold(1i128, |multiplier, instruction| match instruction {
-            Instruction::NewStack => (multiplier * (stack_size - 1)) % stack_size,
-            Instruction::Cut(_x) => multiplier,
+        .try_fold(1i128, |multiplier, instruction| match instruction {
+            Instruction::NewStack => Ok((multiplier * (stack_size - 1)) % stack_size),
+            Instruction::Cut(_x) => Ok(multiplier),
             Instruction::Increment(x) => {
-                let x = modinverse(*x as i128, stack_size).unwrap();
-                (multiplier * x) % stack_size
+                let x = inverse_increment(*x, stack_size)?;
+                Ok((multiplier * x) % stack_size)
             }
         })
 }
 
-fn get_addition(instructions: &[Instruction], stack_size: i128) -> i128 {
+fn get_addition(instructions: &[Instruction], stack_size: i128) -> Result<i128, SolveError> {
     instructions
         .iter()
         .rev()
-        .fold(0i128, |addition, instruction| match instruction {
-            Instruction::NewStack => ((addition + 1) * (stack_size - 1)) % stack_size,
+        .try_fold(0i128, |addition, instruction| match instruction {
+            Instruction::NewStack => Ok(((addition + 1) * (stack_size - 1)) % stack_size),
             Instruction::Cut(x) => {
                 let x = if x < &0 {
                     stack_size + *x as i128
                 } else {
                     *x as i128
                 };
-                (addition + x) % stack_size
+                Ok((addition + x) % stack_size)
             }
             Instruction::Increment(x) => {
-                let x = modinverse(*x as i128, stack_size).unwrap();
-                (addition * x) % stack_size
+                let x = inverse_increment(*x, stack_size)?;
+                Ok((addition * x) % stack_size)
             }
         })
 }
@@ -136,24 +149,26 @@ fn mod_pow(mut base: i128, mut exp: i128, modulus: i128) -> i128 {
     result
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let instructions = parse_instructions(input);
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let instructions = parse_instructions(input)?;
     let stack_size = 119315717514047;
     let repeats = 101741582076661;
     let requested_position = 2020;
 
-    let multiplier = get_multiplier(&instructions, stack_size);
-    let addition = get_addition(&instructions, stack_size);
+    let multiplier = get_multiplier(&instructions, stack_size)?;
+    let addition = get_addition(&instructions, stack_size)?;
 
     let mx = mod_pow(multiplier, repeats, stack_size);
     let pmx = (requested_position * mx) % stack_size;
     let amx = (addition * mx) % stack_size;
-    let inv = modinverse(multiplier - 1, stack_size).unwrap();
+    let inv = modinverse(multiplier - 1, stack_size).ok_or_else(|| {
+        SolveError::no_solution("the shuffle doesn't cycle, so it can't be repeated backwards")
+    })?;
     let res = (pmx + (amx - addition) * inv) % stack_size;
     if res >= 0 {
-        res as usize
+        Ok(res as usize)
     } else {
-        (res + stack_size) as usize
+        Ok((res + stack_size) as usize)
     }
 }
 
@@ -187,29 +202,47 @@ cut -1";
 
     #[test]
     fn test_star_one() {
-        let input = parse_instructions(Cursor::new(INPUT1));
+        let input = parse_instructions(Cursor::new(INPUT1)).unwrap();
         assert_eq!(
             star_one_stack(&input, (0..10).collect()),
             vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7]
         );
-        let input = parse_instructions(Cursor::new(INPUT2));
+        let input = parse_instructions(Cursor::new(INPUT2)).unwrap();
         assert_eq!(
             star_one_stack(&input, (0..10).collect()),
             vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6]
         );
-        let input = parse_instructions(Cursor::new(INPUT3));
+        let input = parse_instructions(Cursor::new(INPUT3)).unwrap();
         assert_eq!(
             star_one_stack(&input, (0..10).collect()),
             vec![6, 3, 0, 7, 4, 1, 8, 5, 2, 9]
         );
 
-        let input = parse_instructions(Cursor::new(INPUT4));
+        let input = parse_instructions(Cursor::new(INPUT4)).unwrap();
         assert_eq!(
             star_one_stack(&input, (0..10).collect()),
             vec![9, 2, 5, 8, 1, 4, 7, 0, 3, 6]
         );
     }
 
+    #[test]
+    fn test_parse_errors() {
+        let error = |text: &str| {
+            parse_instructions(Cursor::new(text))
+                .err()
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(
+            error("cut 3\ndeal with increment x"),
+            "line 2, column 21: bad number \"x\""
+        );
+        assert_eq!(
+            error("shuffle"),
+            "line 1, column 1: unknown technique \"shuffle\""
+        );
+    }
+
     #[test]
     fn test_star_two() {}
 }
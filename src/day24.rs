@@ -3,76 +3,56 @@ use std::{
     io::BufRead,
 };
 
+use crate::{
+    grid::{Grid, Point},
+    parse::ParseError,
+    render::{Animation, Image, Rgb},
+    solution::SolveError,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Space {
     Bug,
     Empty,
 }
 
-impl From<char> for Space {
-    fn from(c: char) -> Self {
+impl Space {
+    fn from_char(c: char) -> Option<Space> {
         match c {
-            '#' => Space::Bug,
-            '.' => Space::Empty,
-            x => panic!("{}", x),
+            '#' => Some(Space::Bug),
+            '.' => Some(Space::Empty),
+            _ => None,
         }
     }
 }
 
-fn parse_map(input: impl BufRead) -> Vec<Vec<Space>> {
-    input
-        .lines()
-        .map(|v| {
-            // println!("{}", &v);
-            v.unwrap().chars().map(Space::from).collect()
-        })
-        .collect()
+fn parse_map(input: impl BufRead) -> Result<Grid<Space>, ParseError> {
+    Grid::parse(input, Space::from_char)
 }
 
-fn count_surrounding(map: &[Vec<Space>], i: usize, j: usize) -> usize {
-    map.get(i.wrapping_sub(1))
-        .and_then(|r| r.get(j))
-        .map(|x| usize::from(matches!(x, Space::Bug)))
-        .unwrap_or(0)
-        + map
-            .get(i + 1)
-            .and_then(|r| r.get(j))
-            .map(|x| usize::from(matches!(x, Space::Bug)))
-            .unwrap_or(0)
-        + map
-            .get(i)
-            .and_then(|r| r.get(j.wrapping_sub(1)))
-            .map(|x| usize::from(matches!(x, Space::Bug)))
-            .unwrap_or(0)
-        + map
-            .get(i)
-            .and_then(|r| r.get(j + 1))
-            .map(|x| usize::from(matches!(x, Space::Bug)))
-            .unwrap_or(0)
+fn count_surrounding(map: &Grid<Space>, position: Point) -> usize {
+    map.neighbours4(position)
+        .filter(|&p| map[p] == Space::Bug)
+        .count()
 }
 
-fn step(map: &[Vec<Space>]) -> Vec<Vec<Space>> {
-    map.iter()
-        .enumerate()
-        .map(|(i, row)| {
-            row.iter()
-                .enumerate()
-                .map(|(j, x)| match (x, count_surrounding(map, i, j)) {
-                    (Space::Bug, 1) => Space::Bug,
-                    (Space::Empty, 1 | 2) => Space::Bug,
-                    (Space::Bug, _) => Space::Empty,
-                    (x, _) => x.clone(),
-                })
-                .collect()
-        })
-        .collect()
+fn step(map: &Grid<Space>) -> Grid<Space> {
+    let mut next = map.clone();
+    for (position, x) in map.iter() {
+        next[position] = match (x, count_surrounding(map, position)) {
+            (Space::Bug, 1) => Space::Bug,
+            (Space::Empty, 1 | 2) => Space::Bug,
+            (Space::Bug, _) => Space::Empty,
+            (x, _) => x.clone(),
+        };
+    }
+    next
 }
 
-fn biodiversity(map: &[Vec<Space>]) -> usize {
+fn biodiversity(map: &Grid<Space>) -> usize {
     map.iter()
-        .flatten()
         .enumerate()
-        .map(|(i, s)| {
+        .map(|(i, (_, s))| {
             if matches!(s, Space::Bug) {
                 2usize.pow(i as u32)
             } else {
@@ -82,19 +62,34 @@ fn biodiversity(map: &[Vec<Space>]) -> usize {
         .sum()
 }
 
-pub fn star_one(input: impl BufRead) -> usize {
-    let mut map = parse_map(input);
+pub fn star_one(input: impl BufRead) -> Result<usize, SolveError> {
+    let mut map = parse_map(input)?;
     let mut seen = HashSet::new();
     loop {
         map = step(&map);
         if seen.contains(&map) {
-            return biodiversity(&map);
+            return Ok(biodiversity(&map));
         } else {
             seen.insert(map.clone());
         }
     }
 }
 
+/// The bugs spreading minute by minute, until a layout repeats.
+pub fn animate(input: impl BufRead) -> Result<Animation, SolveError> {
+    let mut map = parse_map(input)?;
+    let mut seen = HashSet::new();
+    let mut animation = Animation::new();
+    while seen.insert(map.clone()) {
+        animation.push(Image::from_grid(&map, |space| match space {
+            Space::Bug => Rgb::GREEN,
+            Space::Empty => Rgb::BLACK,
+        }));
+        map = step(&map);
+    }
+    Ok(animation)
+}
+
 fn get_adjacent(
     pos: &(usize, usize, isize),
     width: usize,
@@ -180,22 +175,21 @@ fn step2(
     new_map
 }
 
-pub fn star_two(input: impl BufRead) -> usize {
-    let map = parse_map(input);
+pub fn star_two(input: impl BufRead) -> Result<usize, SolveError> {
+    let map = parse_map(input)?;
+    if (map.width(), map.height()) != (5, 5) {
+        return Err(SolveError::no_solution("recursive grids must be 5 by 5"));
+    }
     let mut bug_positions: HashSet<(usize, usize, isize)> = map
-        .into_iter()
-        .enumerate()
-        .flat_map(|(i, row)| {
-            row.into_iter()
-                .enumerate()
-                .filter(|(_j, s)| s == &Space::Bug)
-                .map(move |(j, _s)| (i, j, 0))
-        })
+        .iter()
+        // The middle tile holds the next level down, not a bug.
+        .filter(|&(p, s)| s == &Space::Bug && p != Point::new(2, 2))
+        .map(|(p, _s)| (p.y as usize, p.x as usize, 0))
         .collect();
     for _i in 0..200 {
         bug_positions = step2(bug_positions, 5, 5);
     }
-    bug_positions.len()
+    Ok(bug_positions.len())
 }
 
 #[cfg(test)]
@@ -217,12 +211,23 @@ mod tests {
 
     #[test]
     fn test_star_one() {
-        let input = parse_map(Cursor::new(INPUT1));
-        let expected = parse_map(Cursor::new(EXPECTED1));
+        let input = parse_map(Cursor::new(INPUT1)).unwrap();
+        let expected = parse_map(Cursor::new(EXPECTED1)).unwrap();
         let output = step(&input);
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_animate() {
+        let animation = animate(Cursor::new(INPUT1)).unwrap();
+        let first = &animation.frames()[0];
+        assert_eq!((first.width(), first.height()), (5, 5));
+        assert_eq!(first.pixel(4, 0), Some(Rgb::GREEN));
+        assert_eq!(first.pixel(0, 0), Some(Rgb::BLACK));
+        // The example layout first repeats after 86 minutes.
+        assert_eq!(animation.len(), 86);
+    }
+
     #[test]
     fn test_get_adjacent() {
         assert_eq!(
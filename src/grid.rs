@@ -0,0 +1,408 @@
+//! Two-dimensional grids for the map and maze days: a dense [`Grid`] for rectangular maps read
+//! from the input, and a [`SparseGrid`] for maps discovered a cell at a time with no fixed
+//! bounds. Both are indexed by [`Point`], with `y` growing downwards as in the puzzle text.
+//!
+//! ```
+//! use advent_of_code_2019::grid::{Grid, Point};
+//!
+//! let grid = Grid::parse(&b"#.#\n...\n"[..], |c| Some(c == '#')).unwrap();
+//! assert_eq!(grid.get(Point::new(2, 0)), Some(&true));
+//! assert_eq!(grid.get(Point::new(3, 0)), None);
+//! assert_eq!(grid.neighbours4(Point::new(0, 0)).count(), 2);
+//! assert_eq!(grid.render(|&wall| if wall { '#' } else { '.' }), "#.#\n...");
+//! ```
+
+use std::{
+    collections::HashMap,
+    io::BufRead,
+    ops::{Add, AddAssign, Index, IndexMut, Sub},
+};
+
+use crate::parse::{self, ParseError};
+
+/// A position on a grid. `y` grows downwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point {
+    pub x: isize,
+    pub y: isize,
+}
+
+impl Point {
+    pub const ORIGIN: Point = Point::new(0, 0);
+    pub const UP: Point = Point::new(0, -1);
+    pub const DOWN: Point = Point::new(0, 1);
+    pub const LEFT: Point = Point::new(-1, 0);
+    pub const RIGHT: Point = Point::new(1, 0);
+
+    /// The four orthogonal steps.
+    pub const ADJACENT: [Point; 4] = [Point::UP, Point::DOWN, Point::LEFT, Point::RIGHT];
+
+    /// The eight orthogonal and diagonal steps.
+    pub const SURROUNDING: [Point; 8] = [
+        Point::new(-1, -1),
+        Point::UP,
+        Point::new(1, -1),
+        Point::LEFT,
+        Point::RIGHT,
+        Point::new(-1, 1),
+        Point::DOWN,
+        Point::new(1, 1),
+    ];
+
+    pub const fn new(x: isize, y: isize) -> Point {
+        Point { x, y }
+    }
+
+    /// The four orthogonal neighbours, unbounded.
+    pub fn neighbours4(self) -> impl Iterator<Item = Point> {
+        Point::ADJACENT.into_iter().map(move |step| self + step)
+    }
+
+    /// The eight orthogonal and diagonal neighbours, unbounded.
+    pub fn neighbours8(self) -> impl Iterator<Item = Point> {
+        Point::SURROUNDING.into_iter().map(move |step| self + step)
+    }
+
+    pub fn manhattan(self, other: Point) -> usize {
+        self.x.abs_diff(other.x) + self.y.abs_diff(other.y)
+    }
+}
+
+impl From<(isize, isize)> for Point {
+    fn from((x, y): (isize, isize)) -> Point {
+        Point { x, y }
+    }
+}
+
+impl Add for Point {
+    type Output = Point;
+
+    fn add(self, rhs: Point) -> Point {
+        Point::new(self.x + rhs.x, self.y + rhs.y)
+    }
+}
+
+impl AddAssign for Point {
+    fn add_assign(&mut self, rhs: Point) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Point {
+    type Output = Point;
+
+    fn sub(self, rhs: Point) -> Point {
+        Point::new(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
+/// A rectangular grid stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// A `width` by `height` grid with every cell set to `fill`.
+    pub fn new(width: usize, height: usize, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        Grid {
+            width,
+            height,
+            cells: vec![fill; width * height],
+        }
+    }
+
+    /// Builds a grid from rows, padding short rows with `fill` to the width of the longest.
+    pub fn from_rows(rows: Vec<Vec<T>>, fill: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let height = rows.len();
+        let mut cells = Vec::with_capacity(width * height);
+        for mut row in rows {
+            row.resize(width, fill.clone());
+            cells.extend(row);
+        }
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Parses one row per line, converting each character with `cell`. Every row must be the
+    /// same width; blank lines at the end are ignored.
+    pub fn parse(
+        input: impl BufRead,
+        cell: impl FnMut(char) -> Option<T>,
+    ) -> Result<Grid<T>, ParseError> {
+        let rows = parse::grid(input, cell)?;
+        let width = rows.first().map_or(0, Vec::len);
+        if let Some((y, row)) = rows.iter().enumerate().find(|(_, row)| row.len() != width) {
+            return Err(ParseError::new(
+                y + 1,
+                row.len().min(width) + 1,
+                format!("expected a row of {} cells, found {}", width, row.len()),
+            ));
+        }
+        Ok(Grid {
+            width,
+            height: rows.len(),
+            cells: rows.into_iter().flatten().collect(),
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        (0..self.width as isize).contains(&point.x) && (0..self.height as isize).contains(&point.y)
+    }
+
+    fn offset(&self, point: Point) -> Option<usize> {
+        self.contains(point)
+            .then(|| point.y as usize * self.width + point.x as usize)
+    }
+
+    /// The cell at `point`, or `None` if it is outside the grid.
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.offset(point).map(|offset| &self.cells[offset])
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.offset(point).map(|offset| &mut self.cells[offset])
+    }
+
+    /// Every point in the grid, row by row.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let width = self.width as isize;
+        (0..self.height as isize).flat_map(move |y| (0..width).map(move |x| Point::new(x, y)))
+    }
+
+    /// Every cell with its point, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.points().zip(&self.cells)
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width.max(1))
+    }
+
+    /// The first point, row by row, whose cell matches `predicate`.
+    pub fn position(&self, mut predicate: impl FnMut(&T) -> bool) -> Option<Point> {
+        self.iter()
+            .find(|(_, cell)| predicate(cell))
+            .map(|(point, _)| point)
+    }
+
+    /// The orthogonal neighbours of `point` that are inside the grid.
+    pub fn neighbours4(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours4().filter(|&p| self.contains(p))
+    }
+
+    /// The orthogonal and diagonal neighbours of `point` that are inside the grid.
+    pub fn neighbours8(&self, point: Point) -> impl Iterator<Item = Point> + '_ {
+        point.neighbours8().filter(|&p| self.contains(p))
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    /// Draws the grid with one character per cell and a newline between rows.
+    pub fn render(&self, mut cell: impl FnMut(&T) -> char) -> String {
+        self.rows()
+            .take(self.height)
+            .map(|row| row.iter().map(&mut cell).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<T> Index<Point> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, point: Point) -> &T {
+        match self.get(point) {
+            Some(cell) => cell,
+            None => panic!(
+                "{:?} is outside the {}x{} grid",
+                point, self.width, self.height
+            ),
+        }
+    }
+}
+
+impl<T> IndexMut<Point> for Grid<T> {
+    fn index_mut(&mut self, point: Point) -> &mut T {
+        let (width, height) = (self.width, self.height);
+        match self.get_mut(point) {
+            Some(cell) => cell,
+            None => panic!("{:?} is outside the {}x{} grid", point, width, height),
+        }
+    }
+}
+
+/// A grid with no fixed bounds that only stores the cells that have been set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> SparseGrid<T> {
+        SparseGrid {
+            cells: HashMap::new(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> SparseGrid<T> {
+        SparseGrid::default()
+    }
+
+    pub fn get(&self, point: Point) -> Option<&T> {
+        self.cells.get(&point)
+    }
+
+    pub fn get_mut(&mut self, point: Point) -> Option<&mut T> {
+        self.cells.get_mut(&point)
+    }
+
+    /// Sets a cell, returning its previous value.
+    pub fn insert(&mut self, point: Point, cell: T) -> Option<T> {
+        self.cells.insert(point, cell)
+    }
+
+    pub fn remove(&mut self, point: Point) -> Option<T> {
+        self.cells.remove(&point)
+    }
+
+    pub fn contains(&self, point: Point) -> bool {
+        self.cells.contains_key(&point)
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// Every set cell with its point, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(&point, cell)| (point, cell))
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.cells.values()
+    }
+
+    /// The top left and bottom right corners of the smallest rectangle holding every set cell.
+    pub fn bounds(&self) -> Option<(Point, Point)> {
+        let mut points = self.cells.keys();
+        let first = *points.next()?;
+        Some(points.fold((first, first), |(min, max), p| {
+            (
+                Point::new(min.x.min(p.x), min.y.min(p.y)),
+                Point::new(max.x.max(p.x), max.y.max(p.y)),
+            )
+        }))
+    }
+
+    /// Draws the cells within [`bounds`](SparseGrid::bounds), using `cell` for cells that are
+    /// set and `empty` for those that aren't.
+    pub fn render(&self, empty: char, mut cell: impl FnMut(&T) -> char) -> String {
+        let Some((min, max)) = self.bounds() else {
+            return String::new();
+        };
+        (min.y..=max.y)
+            .map(|y| {
+                (min.x..=max.x)
+                    .map(|x| self.get(Point::new(x, y)).map_or(empty, &mut cell))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl<T> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> SparseGrid<T> {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grid() {
+        let mut grid = Grid::parse(&b"ab\ncd\n\n"[..], Some).unwrap();
+        assert_eq!((grid.width(), grid.height()), (2, 2));
+        assert_eq!(grid[Point::new(1, 1)], 'd');
+        assert_eq!(grid.get(Point::new(-1, 0)), None);
+        assert_eq!(grid.get(Point::new(0, 2)), None);
+        grid[Point::new(0, 1)] = 'x';
+        assert_eq!(grid.position(|&c| c == 'x'), Some(Point::new(0, 1)));
+        assert_eq!(grid.render(|&c| c), "ab\nxd");
+        assert_eq!(
+            grid.map(|c| c.is_alphabetic() && *c != 'x')
+                .iter()
+                .filter(|c| *c.1)
+                .count(),
+            3
+        );
+        assert_eq!(
+            Grid::parse(&b"ab\nc\n"[..], Some).unwrap_err().to_string(),
+            "line 2, column 2: expected a row of 2 cells, found 1"
+        );
+        let padded = Grid::from_rows(vec![vec!['a'], vec!['b', 'c']], ' ');
+        assert_eq!(padded.render(|&c| c), "a \nbc");
+    }
+
+    #[test]
+    fn test_neighbours() {
+        let grid = Grid::new(3, 3, ());
+        let corner: Vec<Point> = grid.neighbours4(Point::ORIGIN).collect();
+        assert_eq!(corner, vec![Point::DOWN, Point::RIGHT]);
+        assert_eq!(grid.neighbours8(Point::ORIGIN).count(), 3);
+        assert_eq!(grid.neighbours4(Point::new(1, 1)).count(), 4);
+        assert_eq!(grid.neighbours8(Point::new(1, 1)).count(), 8);
+        assert_eq!(Point::new(-2, 3).manhattan(Point::new(1, -1)), 7);
+    }
+
+    #[test]
+    fn test_sparse_grid() {
+        let mut grid = SparseGrid::new();
+        assert_eq!(grid.bounds(), None);
+        assert_eq!(grid.render('.', |_: &bool| '#'), "");
+        grid.insert(Point::new(-1, 2), true);
+        grid.insert(Point::new(1, 0), false);
+        assert_eq!(grid.bounds(), Some((Point::new(-1, 0), Point::new(1, 2))));
+        assert_eq!(
+            grid.render(' ', |&on| if on { '#' } else { '.' }),
+            "  .\n   \n#  "
+        );
+    }
+}
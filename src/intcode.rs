@@ -0,0 +1,951 @@
+//! The IntCode virtual machine shared by the IntCode days (2, 5, 7, 9, 11, 13, 15, 17, 19, 21,
+//! 23 and 25).
+//!
+//! ```
+//! use advent_of_code_2019::intcode::{IntCode, IntCodeState};
+//!
+//! // Read a value, add 5 to it and output the result.
+//! let program = vec![3, 9, 1001, 9, 5, 9, 4, 9, 99, 0];
+//! let mut computer = IntCode::builder(program).input(vec![37]).build();
+//! assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![42])));
+//! ```
+
+pub mod ascii;
+pub mod asm;
+#[cfg(test)]
+mod conformance;
+pub mod debugger;
+pub mod decode;
+pub mod disasm;
+#[cfg(test)]
+mod fixtures;
+pub mod io;
+pub mod memory;
+pub mod network;
+pub mod snapshot;
+pub mod topology;
+pub mod trace;
+pub mod word;
+
+use std::{collections::VecDeque, error::Error, fmt::Display};
+
+use decode::Stop;
+use io::{Controller, Input, Output, Wired};
+use memory::Memory;
+use trace::Tracer;
+use word::Word;
+
+/// Why [`IntCode::run`] returned control to the caller.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntCodeState<W = isize> {
+    /// The program executed opcode 99. Holds any output that has not been taken yet.
+    Halted(Vec<W>),
+    /// The requested number of outputs has been buffered.
+    Output(Vec<W>),
+    /// The program wants to read input but the input queue is empty.
+    InputNeeded,
+}
+
+/// The IntCode instruction set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Opcode {
+    Add,
+    Multiply,
+    Input,
+    Output,
+    JumpIfTrue,
+    JumpIfFalse,
+    LessThan,
+    Equals,
+    AdjustRelativeBase,
+    Halt,
+}
+
+impl Opcode {
+    pub const ALL: [Opcode; 10] = [
+        Opcode::Add,
+        Opcode::Multiply,
+        Opcode::Input,
+        Opcode::Output,
+        Opcode::JumpIfTrue,
+        Opcode::JumpIfFalse,
+        Opcode::LessThan,
+        Opcode::Equals,
+        Opcode::AdjustRelativeBase,
+        Opcode::Halt,
+    ];
+
+    /// Decodes the low two digits of an instruction word.
+    pub fn from_code(code: isize) -> Option<Opcode> {
+        match code {
+            1 => Some(Opcode::Add),
+            2 => Some(Opcode::Multiply),
+            3 => Some(Opcode::Input),
+            4 => Some(Opcode::Output),
+            5 => Some(Opcode::JumpIfTrue),
+            6 => Some(Opcode::JumpIfFalse),
+            7 => Some(Opcode::LessThan),
+            8 => Some(Opcode::Equals),
+            9 => Some(Opcode::AdjustRelativeBase),
+            99 => Some(Opcode::Halt),
+            _ => None,
+        }
+    }
+
+    pub fn code(self) -> isize {
+        match self {
+            Opcode::Add => 1,
+            Opcode::Multiply => 2,
+            Opcode::Input => 3,
+            Opcode::Output => 4,
+            Opcode::JumpIfTrue => 5,
+            Opcode::JumpIfFalse => 6,
+            Opcode::LessThan => 7,
+            Opcode::Equals => 8,
+            Opcode::AdjustRelativeBase => 9,
+            Opcode::Halt => 99,
+        }
+    }
+
+    pub fn mnemonic(self) -> &'static str {
+        match self {
+            Opcode::Add => "ADD",
+            Opcode::Multiply => "MUL",
+            Opcode::Input => "IN",
+            Opcode::Output => "OUT",
+            Opcode::JumpIfTrue => "JT",
+            Opcode::JumpIfFalse => "JF",
+            Opcode::LessThan => "LT",
+            Opcode::Equals => "EQ",
+            Opcode::AdjustRelativeBase => "ARB",
+            Opcode::Halt => "HLT",
+        }
+    }
+
+    /// Number of parameters following the instruction word.
+    pub fn parameter_count(self) -> usize {
+        match self {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => 3,
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => 2,
+            Opcode::Input | Opcode::Output | Opcode::AdjustRelativeBase => 1,
+            Opcode::Halt => 0,
+        }
+    }
+
+    /// The (1-based) parameter this instruction writes through, if any.
+    pub fn write_parameter(self) -> Option<usize> {
+        match self {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => Some(3),
+            Opcode::Input => Some(1),
+            _ => None,
+        }
+    }
+}
+
+/// How a parameter is interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// The parameter is an address.
+    Position,
+    /// The parameter is the value itself.
+    Immediate,
+    /// The parameter is an offset from the relative base.
+    Relative,
+}
+
+impl Mode {
+    pub fn from_digit(digit: isize) -> Option<Mode> {
+        match digit {
+            0 => Some(Mode::Position),
+            1 => Some(Mode::Immediate),
+            2 => Some(Mode::Relative),
+            _ => None,
+        }
+    }
+
+    pub fn digit(self) -> isize {
+        match self {
+            Mode::Position => 0,
+            Mode::Immediate => 1,
+            Mode::Relative => 2,
+        }
+    }
+
+    /// The mode digit of parameter `pos` (1-based) in `instruction`.
+    pub fn digit_of(instruction: isize, pos: usize) -> isize {
+        (instruction / 10_isize.pow(pos as u32 + 1)) % 10
+    }
+}
+
+/// The result of [`IntCode::step`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Step<W = isize> {
+    /// An instruction ran and the instruction pointer moved on.
+    Executed(Executed<W>),
+    /// The instruction at the instruction pointer is an input and the input queue is empty.
+    InputNeeded,
+    /// The instruction at the instruction pointer is a halt.
+    Halted,
+}
+
+/// A record of one executed instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Executed<W = isize> {
+    pub ip: usize,
+    pub opcode: Opcode,
+    /// Resolved parameters: values for inputs, addresses for write targets. Only the first
+    /// `opcode.parameter_count()` entries are meaningful.
+    pub operands: [W; 3],
+    /// The addresses the input parameters were read from. Immediate parameters are read from
+    /// the instruction itself.
+    pub reads: [Option<usize>; 2],
+    /// The address and value stored to memory, if any.
+    pub write: Option<(usize, W)>,
+    /// The value emitted by an output instruction.
+    pub output: Option<W>,
+}
+
+/// A fault raised while executing a program. Every variant records the instruction pointer
+/// and the raw instruction word at the time of the fault.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError<W = isize> {
+    /// The low two digits of the instruction are not a known opcode.
+    BadOpcode { ip: usize, instruction: W },
+    /// A parameter mode digit is not 0, 1 or 2.
+    BadMode {
+        ip: usize,
+        instruction: W,
+        mode: isize,
+    },
+    /// A position or relative parameter resolved to an address below 0.
+    NegativeAddress {
+        ip: usize,
+        instruction: W,
+        address: W,
+    },
+    /// A position or relative parameter resolved to an address too large to index memory
+    /// with. Only possible with words wider than `usize`.
+    AddressTooLarge {
+        ip: usize,
+        instruction: W,
+        address: W,
+    },
+    /// A jump targeted an address outside of memory.
+    JumpOutOfBounds {
+        ip: usize,
+        instruction: W,
+        target: W,
+    },
+    /// An instruction tried to write through an immediate mode parameter.
+    WriteToImmediate { ip: usize, instruction: W },
+    /// A write would allocate more memory than the configured limit.
+    MemoryLimit {
+        ip: usize,
+        instruction: W,
+        address: usize,
+    },
+    /// Strict mode only: an instruction or operand was read from beyond the end of memory.
+    ReadPastEnd {
+        ip: usize,
+        instruction: W,
+        address: usize,
+    },
+    /// Strict mode only: the instruction has non-zero mode digits for parameters its opcode
+    /// doesn't have.
+    UnusedModeDigits { ip: usize, instruction: W },
+    /// Checked mode only: an addition or multiplication overflowed the word type.
+    Overflow { ip: usize, instruction: W },
+}
+
+impl<W: Word> Display for VmError<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmError::BadOpcode { ip, instruction } => {
+                let code = instruction.to_isize().map(|code| code % 100);
+                match code {
+                    Some(code) => write!(f, "[{}] {}: unknown opcode {}", ip, instruction, code),
+                    None => write!(f, "[{}] {}: unknown opcode", ip, instruction),
+                }
+            }
+            VmError::BadMode {
+                ip,
+                instruction,
+                mode,
+            } => write!(
+                f,
+                "[{}] {}: unsupported parameter mode {}",
+                ip, instruction, mode
+            ),
+            VmError::NegativeAddress {
+                ip,
+                instruction,
+                address,
+            } => write!(f, "[{}] {}: negative address {}", ip, instruction, address),
+            VmError::AddressTooLarge {
+                ip,
+                instruction,
+                address,
+            } => write!(
+                f,
+                "[{}] {}: address {} is too large",
+                ip, instruction, address
+            ),
+            VmError::JumpOutOfBounds {
+                ip,
+                instruction,
+                target,
+            } => write!(
+                f,
+                "[{}] {}: jump out of bounds to {}",
+                ip, instruction, target
+            ),
+            VmError::WriteToImmediate { ip, instruction } => {
+                write!(
+                    f,
+                    "[{}] {}: write to an immediate parameter",
+                    ip, instruction
+                )
+            }
+            VmError::MemoryLimit {
+                ip,
+                instruction,
+                address,
+            } => write!(
+                f,
+                "[{}] {}: writing address {} exceeds the memory limit",
+                ip, instruction, address
+            ),
+            VmError::ReadPastEnd {
+                ip,
+                instruction,
+                address,
+            } => write!(
+                f,
+                "[{}] {}: read of address {} past the end of memory",
+                ip, instruction, address
+            ),
+            VmError::UnusedModeDigits { ip, instruction } => write!(
+                f,
+                "[{}] {}: mode digits set for parameters the opcode doesn't have",
+                ip, instruction
+            ),
+            VmError::Overflow { ip, instruction } => {
+                write!(f, "[{}] {}: arithmetic overflow", ip, instruction)
+            }
+        }
+    }
+}
+
+impl<W: Word> Error for VmError<W> {}
+
+/// An IntCode computer: memory, instruction pointer, relative base and I/O buffers, generic
+/// over the [`Word`] it computes with. Most code uses the `isize` [`IntCode`].
+#[derive(Debug, Clone)]
+pub struct Vm<W> {
+    program: Memory<W>,
+    input: VecDeque<W>,
+    index: usize,
+    output: Vec<W>,
+    relative_base: W,
+    strict: bool,
+    checked: bool,
+    cache: decode::Cache,
+}
+
+/// The VM used by the puzzles.
+pub type IntCode = Vm<isize>;
+
+/// Builder for [`Vm`], created with [`Vm::builder`].
+#[derive(Debug, Clone)]
+pub struct IntCodeBuilder<W = isize> {
+    program: Vec<W>,
+    input: Vec<W>,
+    relative_base: W,
+    memory_limit: Option<usize>,
+    strict: bool,
+    checked: bool,
+}
+
+impl<W: Word> IntCodeBuilder<W> {
+    /// Sets the values queued for the program's input instructions.
+    pub fn input(mut self, input: Vec<W>) -> Self {
+        self.input = input;
+        self
+    }
+
+    /// Sets the starting relative base (defaults to 0).
+    pub fn relative_base(mut self, relative_base: W) -> Self {
+        self.relative_base = relative_base;
+        self
+    }
+
+    /// Caps the number of memory words the program may allocate. Writes beyond the limit fail
+    /// with [`VmError::MemoryLimit`].
+    pub fn memory_limit(mut self, words: usize) -> Self {
+        self.memory_limit = Some(words);
+        self
+    }
+
+    /// Rejects programs that step outside the day 2 and day 5 specs: reading beyond the end of
+    /// memory ([`VmError::ReadPastEnd`]) and mode digits on parameters the opcode doesn't
+    /// have ([`VmError::UnusedModeDigits`]). Day 9 makes memory past the end readable, so
+    /// later programs generally need the default, lenient mode.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Fails with [`VmError::Overflow`] when arithmetic overflows the word type, rather than
+    /// wrapping around.
+    pub fn checked(mut self, checked: bool) -> Self {
+        self.checked = checked;
+        self
+    }
+
+    pub fn build(self) -> Vm<W> {
+        Vm {
+            program: Memory::new(self.program).with_limit(self.memory_limit),
+            input: self.input.into(),
+            index: 0,
+            output: Vec::new(),
+            relative_base: self.relative_base,
+            strict: self.strict,
+            checked: self.checked,
+            cache: decode::Cache::default(),
+        }
+    }
+}
+
+impl<W: Word> Vm<W> {
+    /// Creates a computer with `program` loaded at address 0 and `input` queued.
+    pub fn new(program: Vec<W>, input: Vec<W>) -> Vm<W> {
+        Vm::builder(program).input(input).build()
+    }
+
+    pub fn builder(program: Vec<W>) -> IntCodeBuilder<W> {
+        IntCodeBuilder {
+            program,
+            input: Vec::new(),
+            relative_base: W::zero(),
+            memory_limit: None,
+            strict: false,
+            checked: false,
+        }
+    }
+
+    /// Replaces any pending input.
+    pub fn set_input(&mut self, input: Vec<W>) {
+        self.input = input.into();
+    }
+
+    /// Appends a value to the pending input.
+    pub fn push_input(&mut self, value: W) {
+        self.input.push_back(value);
+    }
+
+    /// Input that has been queued but not yet read by the program.
+    pub fn input(&self) -> &VecDeque<W> {
+        &self.input
+    }
+
+    /// Output that has been produced but not yet taken.
+    pub fn output(&self) -> &[W] {
+        &self.output
+    }
+
+    /// Removes and returns all buffered output.
+    pub fn take_output(&mut self) -> Vec<W> {
+        self.output.drain(..).collect()
+    }
+
+    /// Reads a memory cell. Memory past the end of the program reads as 0.
+    pub fn read(&self, index: usize) -> W {
+        self.program.read(index)
+    }
+
+    /// Writes a memory cell, allocating memory if needed.
+    pub fn write(&mut self, index: usize, data: W) -> Result<(), VmError<W>> {
+        self.cache.invalidate(index);
+        self.program
+            .write(index, data)
+            .map_err(|_| VmError::MemoryLimit {
+                ip: self.index,
+                instruction: self.read(self.index),
+                address: index,
+            })
+    }
+
+    /// The contiguous memory starting at address 0: the (possibly modified) program and
+    /// anything written just past it. Far away addresses are only visible through
+    /// [`read`](Vm::read).
+    pub fn memory(&self) -> &[W] {
+        self.program.dense()
+    }
+
+    pub fn instruction_pointer(&self) -> usize {
+        self.index
+    }
+
+    pub fn relative_base(&self) -> W {
+        self.relative_base.clone()
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn is_checked(&self) -> bool {
+        self.checked
+    }
+
+    /// In strict mode, fails if `address` is past the end of memory.
+    fn check_read(&self, op: usize, address: usize) -> Result<(), VmError<W>> {
+        if self.strict && address >= self.program.len() {
+            return Err(VmError::ReadPastEnd {
+                ip: op,
+                instruction: self.read(op),
+                address,
+            });
+        }
+        Ok(())
+    }
+
+    fn add(&self, op: usize, a: &W, b: &W) -> Result<W, VmError<W>> {
+        if !self.checked {
+            return Ok(a.wrapping_add(b));
+        }
+        a.checked_add(b).ok_or_else(|| VmError::Overflow {
+            ip: op,
+            instruction: self.read(op),
+        })
+    }
+
+    fn multiply(&self, op: usize, a: &W, b: &W) -> Result<W, VmError<W>> {
+        if !self.checked {
+            return Ok(a.wrapping_mul(b));
+        }
+        a.checked_mul(b).ok_or_else(|| VmError::Overflow {
+            ip: op,
+            instruction: self.read(op),
+        })
+    }
+
+    /// `code` is the instruction word at `op`, already known to fit in an `isize`.
+    fn get_index(&self, op: usize, code: isize, pos: usize) -> Result<usize, VmError<W>> {
+        self.check_read(op, op + pos)?;
+        let mode = Mode::digit_of(code, pos);
+        let address = match Mode::from_digit(mode) {
+            Some(Mode::Position) => self.read(op + pos),
+            Some(Mode::Immediate) => return Ok(op + pos),
+            Some(Mode::Relative) => self.add(op, &self.relative_base, &self.read(op + pos))?,
+            None => {
+                return Err(VmError::BadMode {
+                    ip: op,
+                    instruction: self.read(op),
+                    mode,
+                })
+            }
+        };
+        match address.to_usize() {
+            Some(address) => Ok(address),
+            None if address < W::zero() => Err(VmError::NegativeAddress {
+                ip: op,
+                instruction: self.read(op),
+                address,
+            }),
+            None => Err(VmError::AddressTooLarge {
+                ip: op,
+                instruction: self.read(op),
+                address,
+            }),
+        }
+    }
+
+    fn get_write_index(&self, op: usize, code: isize, pos: usize) -> Result<usize, VmError<W>> {
+        if Mode::digit_of(code, pos) == Mode::Immediate.digit() {
+            return Err(VmError::WriteToImmediate {
+                ip: op,
+                instruction: self.read(op),
+            });
+        }
+        self.get_index(op, code, pos)
+    }
+
+    /// The address a parameter resolves to and the value read from it.
+    fn get_parameter(&self, op: usize, code: isize, pos: usize) -> Result<(usize, W), VmError<W>> {
+        let index = self.get_index(op, code, pos)?;
+        self.check_read(op, index)?;
+        Ok((index, self.read(index)))
+    }
+
+    fn jump(&mut self, target: W) -> Result<(), VmError<W>> {
+        match target.to_usize() {
+            Some(target) if target < self.program.len() => {
+                self.index = target;
+                Ok(())
+            }
+            _ => Err(VmError::JumpOutOfBounds {
+                ip: self.index,
+                instruction: self.read(self.index),
+                target,
+            }),
+        }
+    }
+
+    fn address_word(address: usize) -> W {
+        W::from_usize(address).expect("addresses fit in every word type")
+    }
+
+    /// Executes a single instruction.
+    pub fn step(&mut self) -> Result<Step<W>, VmError<W>> {
+        let ip = self.index;
+        self.check_read(ip, ip)?;
+        let instruction = self.read(ip);
+        let (code, opcode) = match instruction.to_isize() {
+            Some(code) => match Opcode::from_code(code % 100) {
+                Some(opcode) => (code, opcode),
+                None => return Err(VmError::BadOpcode { ip, instruction }),
+            },
+            None => return Err(VmError::BadOpcode { ip, instruction }),
+        };
+        if self.strict && code / 10_isize.pow(opcode.parameter_count() as u32 + 2) != 0 {
+            return Err(VmError::UnusedModeDigits { ip, instruction });
+        }
+        let mut executed = Executed {
+            ip,
+            opcode,
+            operands: std::array::from_fn(|_| W::zero()),
+            reads: [None; 2],
+            write: None,
+            output: None,
+        };
+        match opcode {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+                let (read1, input1) = self.get_parameter(ip, code, 1)?;
+                let (read2, input2) = self.get_parameter(ip, code, 2)?;
+                let output_index = self.get_write_index(ip, code, 3)?;
+                let result = match opcode {
+                    Opcode::Add => self.add(ip, &input1, &input2)?,
+                    Opcode::Multiply => self.multiply(ip, &input1, &input2)?,
+                    // Opcode 7 is less than: if the first parameter is less than the second parameter, it stores 1 in the position given by the third parameter. Otherwise, it stores 0.
+                    Opcode::LessThan if input1 < input2 => W::one(),
+                    // Opcode 8 is equals: if the first parameter is equal to the second parameter, it stores 1 in the position given by the third parameter. Otherwise, it stores 0.
+                    Opcode::Equals if input1 == input2 => W::one(),
+                    _ => W::zero(),
+                };
+                self.write(output_index, result.clone())?;
+                executed.operands = [input1, input2, Self::address_word(output_index)];
+                executed.reads = [Some(read1), Some(read2)];
+                executed.write = Some((output_index, result));
+                self.index += 4;
+            }
+            Opcode::Input => {
+                let output_index = self.get_write_index(ip, code, 1)?;
+                let data = match self.input.pop_front() {
+                    Some(data) => data,
+                    None => return Ok(Step::InputNeeded),
+                };
+                self.write(output_index, data.clone())?;
+                executed.operands[0] = Self::address_word(output_index);
+                executed.write = Some((output_index, data));
+                self.index += 2
+            }
+            Opcode::Output => {
+                let (read, output_parameter) = self.get_parameter(ip, code, 1)?;
+                self.output.push(output_parameter.clone());
+                executed.operands[0] = output_parameter.clone();
+                executed.reads[0] = Some(read);
+                executed.output = Some(output_parameter);
+                self.index += 2;
+            }
+            //Opcode 5 is jump-if-true: if the first parameter is non-zero, it sets the instruction pointer to the value from the second parameter. Otherwise, it does nothing.
+            // Opcode 6 is jump-if-false: if the first parameter is zero, it sets the instruction pointer to the value from the second parameter. Otherwise, it does nothing.
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let (read1, input1) = self.get_parameter(ip, code, 1)?;
+                let (read2, input2) = self.get_parameter(ip, code, 2)?;
+                let jump = if opcode == Opcode::JumpIfTrue {
+                    !input1.is_zero()
+                } else {
+                    input1.is_zero()
+                };
+                if jump {
+                    self.jump(input2.clone())?
+                } else {
+                    self.index += 3
+                }
+                executed.operands = [input1, input2, W::zero()];
+                executed.reads = [Some(read1), Some(read2)];
+            }
+            Opcode::AdjustRelativeBase => {
+                let (read, input1) = self.get_parameter(ip, code, 1)?;
+                self.relative_base = self.add(ip, &self.relative_base, &input1)?;
+                executed.operands[0] = input1;
+                executed.reads[0] = Some(read);
+                self.index += 2
+            }
+            // Halt program
+            Opcode::Halt => return Ok(Step::Halted),
+        }
+        Ok(Step::Executed(executed))
+    }
+
+    /// Runs until the program halts, needs input, or has buffered `output_max` outputs
+    /// (0 means never stop for output). Uses the cached instruction decoding in
+    /// [`decode`], which behaves exactly like repeated calls to [`step`](Vm::step).
+    pub fn run(&mut self, output_max: usize) -> Result<IntCodeState<W>, VmError<W>> {
+        loop {
+            match self.run_cached(output_max > 0)? {
+                Stop::Output if self.output.len() >= output_max => {
+                    return Ok(IntCodeState::Output(self.output.clone()))
+                }
+                Stop::Output => {}
+                Stop::InputNeeded => return Ok(IntCodeState::InputNeeded),
+                Stop::Halted => return Ok(IntCodeState::Halted(self.output.clone())),
+            }
+        }
+    }
+
+    /// As [`run`](Vm::run), passing every executed instruction to `tracer`. Steps through
+    /// the program one [`step`](Vm::step) at a time.
+    pub fn run_traced(
+        &mut self,
+        output_max: usize,
+        tracer: &mut impl Tracer<W>,
+    ) -> Result<IntCodeState<W>, VmError<W>> {
+        loop {
+            match self.step()? {
+                Step::Executed(executed) => {
+                    tracer.trace(&executed);
+                    if executed.output.is_some()
+                        && output_max > 0
+                        && self.output.len() >= output_max
+                    {
+                        return Ok(IntCodeState::Output(self.output.clone()));
+                    }
+                }
+                Step::InputNeeded => return Ok(IntCodeState::InputNeeded),
+                Step::Halted => return Ok(IntCodeState::Halted(self.output.clone())),
+            }
+        }
+    }
+}
+
+impl IntCode {
+    /// Runs until the program halts or `input` runs dry, passing each output to `output` as
+    /// soon as it is produced. Input already queued on the VM is consumed first. Never returns
+    /// [`IntCodeState::Output`].
+    pub fn run_io(
+        &mut self,
+        input: &mut impl Input,
+        output: &mut impl Output,
+    ) -> Result<IntCodeState, VmError> {
+        self.run_controller(&mut Wired { input, output })
+    }
+
+    /// As [`run_io`](IntCode::run_io), with one object supplying input and receiving output.
+    pub fn run_controller(
+        &mut self,
+        controller: &mut impl Controller,
+    ) -> Result<IntCodeState, VmError> {
+        loop {
+            match self.run_cached(true)? {
+                Stop::Output => {
+                    if let Some(value) = self.output.pop() {
+                        controller.output(value);
+                    }
+                }
+                Stop::InputNeeded => match controller.input() {
+                    Some(value) => self.input.push_back(value),
+                    None => return Ok(IntCodeState::InputNeeded),
+                },
+                Stop::Halted => return Ok(IntCodeState::Halted(self.output.clone())),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let computer = IntCode::builder(vec![99])
+            .input(vec![1, 2])
+            .relative_base(5)
+            .build();
+        assert_eq!(computer.input(), &[1, 2]);
+        assert_eq!(computer.relative_base(), 5);
+        assert_eq!(computer.instruction_pointer(), 0);
+    }
+
+    #[test]
+    fn test_input_needed() {
+        let mut computer = IntCode::new(vec![3, 5, 4, 5, 99, 0], vec![]);
+        assert_eq!(computer.run(0), Ok(IntCodeState::InputNeeded));
+        assert_eq!(computer.instruction_pointer(), 0);
+        computer.push_input(7);
+        assert_eq!(computer.run(1), Ok(IntCodeState::Output(vec![7])));
+        assert_eq!(computer.take_output(), vec![7]);
+        assert!(computer.output().is_empty());
+        assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![])));
+    }
+
+    #[test]
+    fn test_memory() {
+        let mut computer = IntCode::new(vec![1, 0, 0, 0, 99], vec![]);
+        computer.run(0).unwrap();
+        assert_eq!(computer.memory(), &[2, 0, 0, 0, 99]);
+        assert_eq!(computer.read(100), 0);
+        computer.write(6, 3).unwrap();
+        assert_eq!(computer.memory(), &[2, 0, 0, 0, 99, 0, 3]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut computer = IntCode::new(vec![1, 0, 0, 0, 42], vec![]);
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::BadOpcode {
+                ip: 4,
+                instruction: 42
+            })
+        );
+
+        let mut computer = IntCode::new(vec![304, 0, 99], vec![]);
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::BadMode {
+                ip: 0,
+                instruction: 304,
+                mode: 3
+            })
+        );
+
+        let mut computer = IntCode::new(vec![109, 19, 204, -34], vec![]);
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::NegativeAddress {
+                ip: 2,
+                instruction: 204,
+                address: -15
+            })
+        );
+
+        let mut computer = IntCode::new(vec![1105, 1, 50], vec![]);
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::JumpOutOfBounds {
+                ip: 0,
+                instruction: 1105,
+                target: 50
+            })
+        );
+
+        let mut computer = IntCode::new(vec![11101, 1, 1, 0, 99], vec![]);
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::WriteToImmediate {
+                ip: 0,
+                instruction: 11101
+            })
+        );
+    }
+
+    #[test]
+    fn test_strict() {
+        // Reads memory past the end, which is allowed unless strict.
+        let program = vec![4, 10, 99];
+        let mut computer = IntCode::new(program.clone(), vec![]);
+        assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![0])));
+        let mut computer = IntCode::builder(program).strict(true).build();
+        assert!(computer.is_strict());
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::ReadPastEnd {
+                ip: 0,
+                instruction: 4,
+                address: 10
+            })
+        );
+
+        // A truncated instruction.
+        let mut computer = IntCode::builder(vec![1101, 1]).strict(true).build();
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::ReadPastEnd {
+                ip: 0,
+                instruction: 1101,
+                address: 2
+            })
+        );
+
+        // Running off the end.
+        let mut computer = IntCode::builder(vec![1101, 1, 1, 0]).strict(true).build();
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::ReadPastEnd {
+                ip: 4,
+                instruction: 0,
+                address: 4
+            })
+        );
+
+        // A mode digit for the missing third parameter of an output.
+        let program = vec![10104, 7, 99];
+        let mut computer = IntCode::new(program.clone(), vec![]);
+        assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![7])));
+        let mut computer = IntCode::builder(program).strict(true).build();
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::UnusedModeDigits {
+                ip: 0,
+                instruction: 10104
+            })
+        );
+        assert_eq!(
+            computer.run(0).unwrap_err().to_string(),
+            "[0] 10104: mode digits set for parameters the opcode doesn't have"
+        );
+
+        // Writing through an immediate parameter is an error in either mode.
+        let mut computer = IntCode::builder(vec![11101, 1, 1, 0, 99])
+            .strict(true)
+            .build();
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::WriteToImmediate {
+                ip: 0,
+                instruction: 11101
+            })
+        );
+    }
+
+    #[test]
+    fn test_run_off_end() {
+        let mut computer = IntCode::new(vec![1101, 1, 1, 5], vec![]);
+        assert!(matches!(
+            computer.run(0),
+            Err(VmError::BadOpcode { ip: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        // Writes to a far relative address without allocating everything before it.
+        let program = vec![109, 1 << 40, 21101, 2, 3, 0, 204, 0, 99];
+        let mut computer = IntCode::new(program.clone(), vec![]);
+        assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![5])));
+        assert_eq!(computer.memory().len(), program.len());
+
+        let mut computer = IntCode::builder(program).memory_limit(100).build();
+        assert_eq!(
+            computer.run(0),
+            Err(VmError::MemoryLimit {
+                ip: 2,
+                instruction: 21101,
+                address: 1 << 40
+            })
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! A text interface for ASCII IntCode programs (days 17, 21 and 25).
+//!
+//! Output values in the ASCII range are collected as text. Anything else, such as the dust
+//! count at the end of day 17 or the hull damage in day 21, is kept separately and is available
+//! through [`AsciiIntCode::value`].
+
+use std::collections::VecDeque;
+
+use super::{IntCode, IntCodeState, VmError};
+
+#[derive(Debug, Clone)]
+pub struct AsciiIntCode {
+    computer: IntCode,
+    text: String,
+    values: Vec<isize>,
+    halted: bool,
+}
+
+impl AsciiIntCode {
+    pub fn new(program: Vec<isize>) -> AsciiIntCode {
+        AsciiIntCode::from_computer(IntCode::new(program, vec![]))
+    }
+
+    pub fn from_computer(computer: IntCode) -> AsciiIntCode {
+        AsciiIntCode {
+            computer,
+            text: String::new(),
+            values: Vec::new(),
+            halted: false,
+        }
+    }
+
+    /// Queues `text` as input, without a trailing newline.
+    pub fn send(&mut self, text: &str) {
+        for c in text.chars() {
+            self.computer.push_input(c as isize);
+        }
+    }
+
+    /// Queues `line` followed by a newline.
+    pub fn send_line(&mut self, line: &str) {
+        self.send(line);
+        self.computer.push_input('\n' as isize);
+    }
+
+    /// Runs until the program waits for input or halts, and returns the text it printed.
+    pub fn read_until_prompt(&mut self) -> Result<String, VmError> {
+        let mut output = Vec::new();
+        let state = self.computer.run_io(&mut VecDeque::new(), &mut output)?;
+        self.halted = matches!(state, IntCodeState::Halted(_));
+        for value in output {
+            match u8::try_from(value) {
+                Ok(c) if c.is_ascii() => self.text.push(c as char),
+                _ => self.values.push(value),
+            }
+        }
+        Ok(self.read_text())
+    }
+
+    /// Takes the text printed so far without running the program.
+    pub fn read_text(&mut self) -> String {
+        std::mem::take(&mut self.text)
+    }
+
+    /// The last non-ASCII value the program output, if any.
+    pub fn value(&self) -> Option<isize> {
+        self.values.last().copied()
+    }
+
+    /// Every non-ASCII value the program has output.
+    pub fn values(&self) -> &[isize] {
+        &self.values
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.halted
+    }
+
+    pub fn computer(&self) -> &IntCode {
+        &self.computer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    #[test]
+    fn test_prompt() {
+        // Prints a prompt, echoes one character back, then outputs 1000 and halts.
+        let program = assemble(
+            "       OUT #62
+                    OUT #10
+                    IN [c]
+                    OUT [c]
+                    OUT #1000
+                    HLT
+             c:     DATA 0",
+        )
+        .unwrap();
+        let mut computer = AsciiIntCode::new(program);
+        assert_eq!(computer.read_until_prompt(), Ok(">\n".to_string()));
+        assert!(!computer.is_halted());
+        assert_eq!(computer.value(), None);
+
+        computer.send_line("x");
+        assert_eq!(computer.read_until_prompt(), Ok("x".to_string()));
+        assert!(computer.is_halted());
+        assert_eq!(computer.value(), Some(1000));
+        assert_eq!(computer.computer().input(), &[10]);
+    }
+}
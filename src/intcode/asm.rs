@@ -0,0 +1,289 @@
+//! A small assembly language for writing IntCode programs by hand.
+//!
+//! ```text
+//! ; Count down from 3, printing each number.
+//! start:  OUT [counter]
+//!         ADD [counter], #-1, [counter]
+//!         JT [counter], #start
+//!         HLT
+//! counter: DATA 3
+//! ```
+//!
+//! Operands use the same syntax as the [disassembler](super::disasm): `[addr]` for position
+//! mode, `#value` for immediate mode and `rb+offset` for relative mode. Addresses and values
+//! may be labels. `DATA` emits raw words, `;` starts a comment, and a line may start with a
+//! numeric address (`0004:`) as produced by [`listing`](super::disasm::listing), which must
+//! match the address the line is assembled at.
+
+use std::{collections::HashMap, error::Error, fmt::Display};
+
+use nom::{
+    branch::alt,
+    bytes::complete::{tag, tag_no_case},
+    character::complete::{alpha1, alphanumeric1, char, digit1, one_of, space0},
+    combinator::{all_consuming, map, map_res, opt, recognize},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, terminated},
+    IResult, Parser,
+};
+
+use super::{
+    disasm::{Instruction, Operand},
+    Mode, Opcode,
+};
+
+/// An assembly failure on a (1-based) source line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for AsmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for AsmError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Value<'a> {
+    Number(isize),
+    Label(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Body<'a> {
+    Instruction(Opcode, Vec<(Mode, Value<'a>)>),
+    Data(Vec<Value<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Statement<'a> {
+    address: Option<usize>,
+    label: Option<&'a str>,
+    body: Option<Body<'a>>,
+}
+
+fn identifier(s: &str) -> IResult<&str, &str> {
+    recognize(pair(
+        alt((alpha1, tag("_"))),
+        many0(alt((alphanumeric1, tag("_")))),
+    ))
+    .parse(s)
+}
+
+fn number(s: &str) -> IResult<&str, isize> {
+    map_res(recognize(pair(opt(one_of("+-")), digit1)), str::parse).parse(s)
+}
+
+fn value(s: &str) -> IResult<&str, Value<'_>> {
+    alt((map(number, Value::Number), map(identifier, Value::Label))).parse(s)
+}
+
+fn operand(s: &str) -> IResult<&str, (Mode, Value<'_>)> {
+    alt((
+        map(delimited(char('['), value, char(']')), |v| {
+            (Mode::Position, v)
+        }),
+        map(preceded(char('#'), value), |v| (Mode::Immediate, v)),
+        map(preceded(tag_no_case("rb"), number), |n| {
+            (Mode::Relative, Value::Number(n))
+        }),
+    ))
+    .parse(s)
+}
+
+fn separator(s: &str) -> IResult<&str, char> {
+    delimited(space0, char(','), space0).parse(s)
+}
+
+fn body(s: &str) -> IResult<&str, Body<'_>> {
+    let (rest, mnemonic) = alpha1(s)?;
+    if mnemonic.eq_ignore_ascii_case("data") {
+        let (rest, values) = preceded(space0, separated_list0(separator, value)).parse(rest)?;
+        return Ok((rest, Body::Data(values)));
+    }
+    let opcode = Opcode::ALL
+        .into_iter()
+        .find(|opcode| opcode.mnemonic().eq_ignore_ascii_case(mnemonic))
+        .ok_or_else(|| nom::Err::Error(nom::error::Error::new(s, nom::error::ErrorKind::Tag)))?;
+    let (rest, operands) = preceded(space0, separated_list0(separator, operand)).parse(rest)?;
+    Ok((rest, Body::Instruction(opcode, operands)))
+}
+
+fn statement(s: &str) -> IResult<&str, Statement<'_>> {
+    let (rest, (address, label, body)) = all_consuming(delimited(
+        space0,
+        (
+            opt(terminated(
+                map_res(digit1, str::parse),
+                (space0, char(':'), space0),
+            )),
+            opt(terminated(identifier, (space0, char(':'), space0))),
+            opt(body),
+        ),
+        space0,
+    ))
+    .parse(s)?;
+    Ok((
+        rest,
+        Statement {
+            address,
+            label,
+            body,
+        },
+    ))
+}
+
+/// Assembles `source` into a program that can be passed to [`IntCode::new`](super::IntCode::new).
+pub fn assemble(source: &str) -> Result<Vec<isize>, AsmError> {
+    let mut statements = Vec::new();
+    let mut labels = HashMap::new();
+    let mut address = 0;
+    for (i, line) in source.lines().enumerate() {
+        let line_number = i + 1;
+        let error = |message: String| AsmError {
+            line: line_number,
+            message,
+        };
+        let code = line.split(';').next().unwrap();
+        let (_, statement) =
+            statement(code).map_err(|_| error(format!("unable to parse {:?}", code.trim())))?;
+        if let Some(expected) = statement.address {
+            if expected != address {
+                return Err(error(format!(
+                    "line is at address {} but is marked {}",
+                    address, expected
+                )));
+            }
+        }
+        if let Some(label) = statement.label {
+            if labels.insert(label, address).is_some() {
+                return Err(error(format!("duplicate label {}", label)));
+            }
+        }
+        address += match &statement.body {
+            Some(Body::Instruction(opcode, operands)) => {
+                if operands.len() != opcode.parameter_count() {
+                    return Err(error(format!(
+                        "{} takes {} operands but {} were given",
+                        opcode.mnemonic(),
+                        opcode.parameter_count(),
+                        operands.len()
+                    )));
+                }
+                if let Some(pos) = opcode.write_parameter() {
+                    if operands[pos - 1].0 == Mode::Immediate {
+                        return Err(error(format!(
+                            "{} cannot write to an immediate operand",
+                            opcode.mnemonic()
+                        )));
+                    }
+                }
+                operands.len() + 1
+            }
+            Some(Body::Data(values)) => values.len(),
+            None => 0,
+        };
+        statements.push((line_number, statement));
+    }
+
+    let mut program = Vec::with_capacity(address);
+    for (line_number, statement) in statements {
+        let resolve = |value: &Value| match value {
+            Value::Number(n) => Ok(*n),
+            Value::Label(label) => labels
+                .get(label)
+                .map(|&a| a as isize)
+                .ok_or_else(|| AsmError {
+                    line: line_number,
+                    message: format!("unknown label {}", label),
+                }),
+        };
+        match statement.body {
+            Some(Body::Instruction(opcode, operands)) => {
+                let operands = operands
+                    .iter()
+                    .map(|(mode, value)| {
+                        Ok(Operand {
+                            mode: *mode,
+                            value: resolve(value)?,
+                        })
+                    })
+                    .collect::<Result<_, AsmError>>()?;
+                program.extend(Instruction { opcode, operands }.encode());
+            }
+            Some(Body::Data(values)) => {
+                for value in &values {
+                    program.push(resolve(value)?);
+                }
+            }
+            None => {}
+        }
+    }
+    Ok(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{disasm::listing, IntCode};
+
+    #[test]
+    fn test_assemble() {
+        let source = "\
+            ; Count down from 3, printing each number.
+            start:  OUT [counter]
+                    add [counter], #-1, [counter]   ; mnemonics are case-insensitive
+                    JT [counter], #start
+                    HLT
+            counter: DATA 3";
+        let program = assemble(source).unwrap();
+        assert_eq!(program, vec![4, 10, 1001, 10, -1, 10, 1005, 10, 0, 99, 3]);
+
+        let mut computer = IntCode::new(program, vec![]);
+        computer.run(0).unwrap();
+        assert_eq!(computer.take_output(), vec![3, 2, 1]);
+    }
+
+    #[test]
+    fn test_relative() {
+        let program = assemble("ARB #2000\nARB #19\nOUT rb-34\nHLT").unwrap();
+        assert_eq!(program, vec![109, 2000, 109, 19, 204, -34, 99]);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let programs = [
+            vec![
+                109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+            ],
+            vec![
+                3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28,
+                -1, 28, 1005, 28, 6, 99, 0, 0, 5,
+            ],
+            vec![1002, 4, 3, 4, 33],
+        ];
+        for program in programs {
+            assert_eq!(assemble(&listing(&program)), Ok(program));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            assemble("HLT\nADD #1, #2"),
+            Err(AsmError {
+                line: 2,
+                message: "ADD takes 3 operands but 2 were given".to_string()
+            })
+        );
+        assert_eq!(assemble("IN #1").unwrap_err().line, 1);
+        assert_eq!(assemble("JT #1, #nowhere").unwrap_err().line, 1);
+        assert_eq!(assemble("a: HLT\na: HLT").unwrap_err().line, 2);
+        assert_eq!(assemble("0001: HLT").unwrap_err().line, 1);
+        assert_eq!(assemble("NOP").unwrap_err().line, 1);
+    }
+}
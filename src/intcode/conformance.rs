@@ -0,0 +1,408 @@
+//! Conformance tests for the VM: every opcode in every addressing mode, the relative base,
+//! memory growth, large values, overflow and wide words, halting, and the sample programs from
+//! days 2, 5 and 9.
+//!
+//! Most of the mode tests share a layout: the relative base is set to 10 by `ARB #10`, and the
+//! operands live at address 20 onwards, so position mode `20`, relative mode `10` and immediate
+//! mode with the value itself all name the same operand.
+
+use num::BigInt;
+
+use super::{IntCode, IntCodeState, Mode, Vm, VmError};
+
+const MODES: [Mode; 3] = [Mode::Position, Mode::Immediate, Mode::Relative];
+const WRITE_MODES: [Mode; 2] = [Mode::Position, Mode::Relative];
+
+type Expected = fn(isize, isize) -> isize;
+type Predicate = fn(isize) -> bool;
+
+/// Runs a program to completion, returning its output and the halted VM. Also runs it one
+/// step at a time and checks that the cached decoding behind `run` ends up in the same state.
+fn run_with(program: &[isize], input: &[isize], strict: bool) -> (Vec<isize>, IntCode) {
+    let mut computer = IntCode::builder(program.to_vec())
+        .input(input.to_vec())
+        .strict(strict)
+        .build();
+    let mut stepped = computer.clone();
+    let state = computer.run(0);
+    assert_eq!(stepped.run_traced(0, &mut ()), state);
+    assert_eq!(stepped.memory(), computer.memory());
+    match state {
+        Ok(IntCodeState::Halted(output)) => (output, computer),
+        state => panic!("{:?} did not halt: {:?}", program, state),
+    }
+}
+
+fn run(program: &[isize], input: &[isize]) -> (Vec<isize>, IntCode) {
+    run_with(program, input, false)
+}
+
+fn output(program: &[isize], input: &[isize]) -> Vec<isize> {
+    run(program, input).0
+}
+
+/// The day 2 and day 5 samples predate readable memory past the end, so they should also run
+/// in strict mode.
+fn run_strict(program: &[isize], input: &[isize]) -> (Vec<isize>, IntCode) {
+    run_with(program, input, true)
+}
+
+fn strict_output(program: &[isize], input: &[isize]) -> Vec<isize> {
+    run_strict(program, input).0
+}
+
+/// Builds an instruction from an opcode and its parameter modes.
+fn instruction(opcode: isize, modes: &[Mode]) -> isize {
+    modes
+        .iter()
+        .enumerate()
+        .map(|(i, mode)| mode.digit() * 10_isize.pow(i as u32 + 2))
+        .sum::<isize>()
+        + opcode
+}
+
+/// A program with `code` at address 2, the relative base at 10 and `data` at address 20.
+fn layout(code: &[isize], data: &[isize]) -> Vec<isize> {
+    let mut program = vec![109, 10];
+    program.extend(code);
+    assert!(program.len() <= 20);
+    program.resize(20, 0);
+    program.extend(data);
+    program
+}
+
+/// The parameter that reads the word at `20 + offset` in each mode.
+fn read_param(mode: Mode, offset: isize, data: &[isize]) -> isize {
+    match mode {
+        Mode::Position => 20 + offset,
+        Mode::Immediate => data[offset as usize],
+        Mode::Relative => 10 + offset,
+    }
+}
+
+/// The parameter that writes the word at `20 + offset` in each write mode.
+fn write_param(mode: Mode, offset: isize) -> isize {
+    match mode {
+        Mode::Position => 20 + offset,
+        Mode::Relative => 10 + offset,
+        Mode::Immediate => unreachable!(),
+    }
+}
+
+#[test]
+fn test_arithmetic_and_comparisons() {
+    let cases: [(isize, Expected); 4] = [
+        (1, |a, b| a + b),
+        (2, |a, b| a * b),
+        (7, |a, b| (a < b) as isize),
+        (8, |a, b| (a == b) as isize),
+    ];
+    for data in [[7, 5, 0], [5, 7, 0], [-6, -6, 0]] {
+        for (opcode, expected) in cases {
+            for a in MODES {
+                for b in MODES {
+                    for c in WRITE_MODES {
+                        let code = [
+                            instruction(opcode, &[a, b, c]),
+                            read_param(a, 0, &data),
+                            read_param(b, 1, &data),
+                            write_param(c, 2),
+                            4,
+                            22,
+                            99,
+                        ];
+                        let program = layout(&code, &data);
+                        assert_eq!(
+                            output(&program, &[]),
+                            vec![expected(data[0], data[1])],
+                            "{:?}",
+                            program
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_input_and_output() {
+    for mode in WRITE_MODES {
+        let code = [instruction(3, &[mode]), write_param(mode, 0), 4, 20, 99];
+        assert_eq!(output(&layout(&code, &[0]), &[-17]), vec![-17]);
+    }
+    for mode in MODES {
+        let code = [instruction(4, &[mode]), read_param(mode, 0, &[42]), 99];
+        assert_eq!(output(&layout(&code, &[42]), &[]), vec![42]);
+    }
+}
+
+#[test]
+fn test_jumps() {
+    // Outputs 1 if the jump to address 12 is taken and 0 if not.
+    for condition in [0, 1, 5, -3] {
+        for (opcode, taken) in [(5, condition != 0), (6, condition == 0)] {
+            for a in MODES {
+                for b in MODES {
+                    let data = [condition, 12];
+                    let code = [
+                        instruction(opcode, &[a, b]),
+                        read_param(a, 0, &data),
+                        read_param(b, 1, &data),
+                        104,
+                        0,
+                        99,
+                        0,
+                        0,
+                        0,
+                        0,
+                        104,
+                        1,
+                        99,
+                    ];
+                    let program = layout(&code, &data);
+                    assert_eq!(output(&program, &[]), vec![taken as isize], "{:?}", program);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_relative_base() {
+    // Each addressing mode for ARB itself.
+    let mut program = vec![9, 20, 204, 0, 99];
+    program.resize(20, 0);
+    program.push(20);
+    assert_eq!(output(&program, &[]), vec![20]);
+    assert_eq!(
+        output(&layout(&[209, 10, 204, 0, 99], &[10]), &[]),
+        vec![10]
+    );
+    assert_eq!(
+        output(&layout(&[109, 10, 204, 0, 99], &[33]), &[]),
+        vec![33]
+    );
+
+    // The base can move backwards, and the offset can be negative.
+    assert_eq!(output(&layout(&[109, -5, 204, 15, 99], &[8]), &[]), vec![8]);
+    assert_eq!(
+        output(&layout(&[109, 20, 204, -10, 99], &[9]), &[]),
+        vec![9]
+    );
+
+    // Relative writes far past the program grow memory.
+    let (out, computer) = run(&[109, 1000, 21101, 1, 2, 0, 204, 0, 99], &[]);
+    assert_eq!(out, vec![3]);
+    assert_eq!(computer.relative_base(), 1000);
+    assert_eq!(computer.read(1000), 3);
+
+    // The example from day 9, with a starting base of 2000.
+    let mut computer = IntCode::builder(vec![109, 19, 204, -34, 99])
+        .relative_base(2000)
+        .build();
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![0])));
+    assert_eq!(computer.relative_base(), 2019);
+
+    // A negative relative address is an error rather than a wrap around.
+    let mut computer = IntCode::new(vec![109, -1, 204, 0, 99], vec![]);
+    assert_eq!(
+        computer.run(0),
+        Err(VmError::NegativeAddress {
+            ip: 2,
+            instruction: 204,
+            address: -1
+        })
+    );
+}
+
+#[test]
+fn test_memory_growth() {
+    // Reading past the end gives 0, and writing past it extends memory.
+    let (out, computer) = run(&[1101, 1, 2, 100, 4, 100, 4, 50, 99], &[]);
+    assert_eq!(out, vec![3, 0]);
+    assert_eq!(computer.memory().len(), 101);
+    assert_eq!(computer.memory()[9..100], [0; 91]);
+
+    // Far away addresses don't need the memory in between.
+    let address = 1 << 40;
+    let (out, computer) = run(&[1101, 1, 2, address, 4, address, 99], &[]);
+    assert_eq!(out, vec![3]);
+    assert_eq!(computer.memory().len(), 7);
+    assert_eq!(computer.read(address as usize), 3);
+}
+
+#[test]
+fn test_large_values() {
+    assert_eq!(output(&[104, isize::MAX, 99], &[]), vec![isize::MAX]);
+    assert_eq!(output(&[104, isize::MIN, 99], &[]), vec![isize::MIN]);
+    assert_eq!(
+        output(&[1102, 3037000499, 3037000499, 7, 4, 7, 99, 0], &[]),
+        vec![9223372030926249001]
+    );
+    assert_eq!(
+        output(&[1101, isize::MAX, isize::MIN, 7, 4, 7, 99, 0], &[]),
+        vec![-1]
+    );
+    assert_eq!(
+        output(&[1107, isize::MIN, isize::MAX, 7, 4, 7, 99, 0], &[]),
+        vec![1]
+    );
+    assert_eq!(output(&[3, 0, 4, 0, 99], &[1 << 62]), vec![1 << 62]);
+}
+
+#[test]
+fn test_overflow() {
+    // 10^18 squared: wraps by default, fails in checked mode and fits in the wider words.
+    let square = [
+        1102,
+        1_000_000_000_000_000_000,
+        1_000_000_000_000_000_000,
+        7,
+        4,
+        7,
+        99,
+        0,
+    ];
+    let wrapped = 1_000_000_000_000_000_000_isize.wrapping_mul(1_000_000_000_000_000_000);
+    assert_eq!(output(&square, &[]), vec![wrapped]);
+    let mut computer = IntCode::builder(square.to_vec()).checked(true).build();
+    assert_eq!(
+        computer.run(0),
+        Err(VmError::Overflow {
+            ip: 0,
+            instruction: 1102
+        })
+    );
+    let mut computer = IntCode::builder(vec![1101, isize::MAX, 1, 7, 4, 7, 99, 0])
+        .checked(true)
+        .build();
+    assert!(matches!(computer.run(0), Err(VmError::Overflow { .. })));
+
+    let expected = 10_i128.pow(36);
+    let program: Vec<i128> = square.iter().map(|&word| word as i128).collect();
+    let mut computer = Vm::builder(program).checked(true).build();
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![expected])));
+
+    let program: Vec<BigInt> = square.iter().map(|&word| BigInt::from(word)).collect();
+    let mut computer = Vm::new(program, vec![]);
+    assert_eq!(
+        computer.run(0),
+        Ok(IntCodeState::Halted(vec![BigInt::from(expected)]))
+    );
+
+    // Wide words still have to be valid addresses.
+    let mut computer = Vm::<i128>::new(vec![4, 1 << 80, 99], vec![]);
+    assert!(matches!(
+        computer.run(0),
+        Err(VmError::AddressTooLarge { ip: 0, .. })
+    ));
+}
+
+#[test]
+fn test_halting() {
+    // Halting leaves the instruction pointer on the halt, so running again halts again.
+    let mut computer = IntCode::new(vec![104, 1, 104, 2, 99], vec![]);
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![1, 2])));
+    assert_eq!(computer.instruction_pointer(), 4);
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![1, 2])));
+    assert_eq!(computer.take_output(), vec![1, 2]);
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![])));
+
+    // Stopping for output leaves the VM ready to carry on from the next instruction.
+    let mut computer = IntCode::new(vec![104, 1, 104, 2, 99], vec![]);
+    assert_eq!(computer.run(1), Ok(IntCodeState::Output(vec![1])));
+    assert_eq!(computer.instruction_pointer(), 2);
+    assert_eq!(computer.run(2), Ok(IntCodeState::Output(vec![1, 2])));
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![1, 2])));
+
+    // Waiting for input leaves the instruction pointer on the input.
+    let mut computer = IntCode::new(vec![3, 5, 4, 5, 99, 0], vec![]);
+    assert_eq!(computer.run(0), Ok(IntCodeState::InputNeeded));
+    assert_eq!(computer.instruction_pointer(), 0);
+    computer.push_input(6);
+    assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![6])));
+
+    // Input that is never read stays queued.
+    let (_, computer) = run(&[99], &[1, 2]);
+    assert_eq!(computer.input(), &[1, 2]);
+}
+
+#[test]
+fn test_day02_samples() {
+    let samples: [(&[isize], &[isize]); 5] = [
+        (
+            &[1, 9, 10, 3, 2, 3, 11, 0, 99, 30, 40, 50],
+            &[3500, 9, 10, 70, 2, 3, 11, 0, 99, 30, 40, 50],
+        ),
+        (&[1, 0, 0, 0, 99], &[2, 0, 0, 0, 99]),
+        (&[2, 3, 0, 3, 99], &[2, 3, 0, 6, 99]),
+        (&[2, 4, 4, 5, 99, 0], &[2, 4, 4, 5, 99, 9801]),
+        (
+            &[1, 1, 1, 4, 99, 5, 6, 0, 99],
+            &[30, 1, 1, 4, 2, 5, 6, 0, 99],
+        ),
+    ];
+    for (program, expected) in samples {
+        assert_eq!(run_strict(program, &[]).1.memory(), expected);
+    }
+}
+
+#[test]
+fn test_day05_samples() {
+    assert_eq!(strict_output(&[3, 0, 4, 0, 99], &[77]), vec![77]);
+    assert_eq!(
+        run_strict(&[1002, 4, 3, 4, 33], &[]).1.memory(),
+        [1002, 4, 3, 4, 99]
+    );
+    assert_eq!(
+        run_strict(&[1101, 100, -1, 4, 0], &[]).1.memory(),
+        [1101, 100, -1, 4, 99]
+    );
+
+    let comparisons: [(&[isize], Predicate); 4] = [
+        (&[3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8], |x| x == 8),
+        (&[3, 9, 7, 9, 10, 9, 4, 9, 99, -1, 8], |x| x < 8),
+        (&[3, 3, 1108, -1, 8, 3, 4, 3, 99], |x| x == 8),
+        (&[3, 3, 1107, -1, 8, 3, 4, 3, 99], |x| x < 8),
+    ];
+    let jumps: [&[isize]; 2] = [
+        &[3, 12, 6, 12, 15, 1, 13, 14, 13, 4, 13, 99, -1, 0, 1, 9],
+        &[3, 3, 1105, -1, 9, 1101, 0, 0, 12, 4, 12, 99, 1],
+    ];
+    let larger = [
+        3, 21, 1008, 21, 8, 20, 1005, 20, 22, 107, 8, 21, 20, 1006, 20, 31, 1106, 0, 36, 98, 0, 0,
+        1002, 21, 125, 20, 4, 20, 1105, 1, 46, 104, 999, 1105, 1, 46, 1101, 1000, 1, 20, 4, 20,
+        1105, 1, 46, 98, 99,
+    ];
+    for x in [-8, 0, 1, 7, 8, 9, 100] {
+        for (program, expected) in comparisons {
+            assert_eq!(strict_output(program, &[x]), vec![expected(x) as isize]);
+        }
+        for program in jumps {
+            assert_eq!(strict_output(program, &[x]), vec![(x != 0) as isize]);
+        }
+        let expected = match x.cmp(&8) {
+            std::cmp::Ordering::Less => 999,
+            std::cmp::Ordering::Equal => 1000,
+            std::cmp::Ordering::Greater => 1001,
+        };
+        assert_eq!(strict_output(&larger, &[x]), vec![expected]);
+    }
+}
+
+#[test]
+fn test_day09_samples() {
+    let quine = [
+        109, 1, 204, -1, 1001, 100, 1, 100, 1008, 100, 16, 101, 1006, 101, 0, 99,
+    ];
+    assert_eq!(output(&quine, &[]), quine);
+    assert_eq!(
+        output(&[1102, 34915192, 34915192, 7, 4, 7, 99, 0], &[]),
+        vec![1219070632396864]
+    );
+    assert_eq!(
+        output(&[104, 1125899906842624, 99], &[]),
+        vec![1125899906842624]
+    );
+}
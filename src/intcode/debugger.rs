@@ -0,0 +1,420 @@
+//! An interactive debugger for the IntCode VM, built on [`IntCode::step`].
+//!
+//! The [`Debugger`] can be driven programmatically, or through [`Debugger::repl`] which reads
+//! commands line by line (type `help` for a summary).
+
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt::Display,
+    io::{self, BufRead, Write},
+};
+
+use super::{disasm, Executed, IntCode, Opcode, Step, VmError};
+
+/// Why the debugger stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// A single step finished.
+    Stepped(Executed),
+    /// The instruction pointer reached a breakpoint address.
+    Breakpoint(usize),
+    /// The next instruction has an opcode that is being broken on.
+    OpcodeBreak(Opcode, usize),
+    /// An instruction wrote to a watched address.
+    Watchpoint(Executed),
+    /// An output instruction ran.
+    Output(isize),
+    /// The next instruction is an input.
+    Input(usize),
+    /// The program needs input that has not been queued.
+    InputNeeded,
+    Halted,
+}
+
+impl Display for Stop {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stop::Stepped(executed) => write!(
+                f,
+                "stepped {} at {}",
+                executed.opcode.mnemonic(),
+                executed.ip
+            ),
+            Stop::Breakpoint(ip) => write!(f, "breakpoint at {}", ip),
+            Stop::OpcodeBreak(opcode, ip) => write!(f, "{} at {}", opcode.mnemonic(), ip),
+            Stop::Watchpoint(executed) => {
+                let (address, value) = executed.write.unwrap();
+                write!(
+                    f,
+                    "watchpoint: [{}] = {} (written at {})",
+                    address, value, executed.ip
+                )
+            }
+            Stop::Output(value) => write!(f, "output {}", value),
+            Stop::Input(ip) => write!(f, "input at {}", ip),
+            Stop::InputNeeded => write!(f, "waiting for input"),
+            Stop::Halted => write!(f, "halted"),
+        }
+    }
+}
+
+/// How far [`Debugger::resume`] should run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Until {
+    /// Execute one instruction.
+    Step,
+    /// Run until a breakpoint, watchpoint, missing input or halt.
+    Break,
+    /// As `Break`, but also stop after the next output.
+    Output,
+    /// As `Break`, but also stop before the next input instruction.
+    Input,
+}
+
+#[derive(Debug, Clone)]
+pub struct Debugger {
+    computer: IntCode,
+    breakpoints: BTreeSet<usize>,
+    opcode_breakpoints: HashSet<Opcode>,
+    watchpoints: BTreeSet<usize>,
+}
+
+impl Debugger {
+    pub fn new(computer: IntCode) -> Debugger {
+        Debugger {
+            computer,
+            breakpoints: BTreeSet::new(),
+            opcode_breakpoints: HashSet::new(),
+            watchpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn computer(&self) -> &IntCode {
+        &self.computer
+    }
+
+    pub fn computer_mut(&mut self) -> &mut IntCode {
+        &mut self.computer
+    }
+
+    pub fn into_inner(self) -> IntCode {
+        self.computer
+    }
+
+    /// Stops before executing the instruction at `address`.
+    pub fn add_breakpoint(&mut self, address: usize) {
+        self.breakpoints.insert(address);
+    }
+
+    pub fn remove_breakpoint(&mut self, address: usize) -> bool {
+        self.breakpoints.remove(&address)
+    }
+
+    /// Stops before executing any instruction with `opcode`.
+    pub fn break_on_opcode(&mut self, opcode: Opcode) {
+        self.opcode_breakpoints.insert(opcode);
+    }
+
+    /// Stops after any instruction writes to `address`.
+    pub fn add_watchpoint(&mut self, address: usize) {
+        self.watchpoints.insert(address);
+    }
+
+    pub fn remove_watchpoint(&mut self, address: usize) -> bool {
+        self.watchpoints.remove(&address)
+    }
+
+    fn next_opcode(&self) -> Option<Opcode> {
+        let ip = self.computer.instruction_pointer();
+        Opcode::from_code(self.computer.read(ip) % 100)
+    }
+
+    /// Runs until the condition in `until` is met. The instruction at the current instruction
+    /// pointer always runs, so resuming from a breakpoint makes progress.
+    pub fn resume(&mut self, until: Until) -> Result<Stop, VmError> {
+        let mut first = true;
+        loop {
+            let ip = self.computer.instruction_pointer();
+            if !first {
+                if self.breakpoints.contains(&ip) {
+                    return Ok(Stop::Breakpoint(ip));
+                }
+                if let Some(opcode) = self.next_opcode() {
+                    if self.opcode_breakpoints.contains(&opcode) {
+                        return Ok(Stop::OpcodeBreak(opcode, ip));
+                    }
+                    if until == Until::Input && opcode == Opcode::Input {
+                        return Ok(Stop::Input(ip));
+                    }
+                }
+            }
+            first = false;
+            let executed = match self.computer.step()? {
+                Step::Executed(executed) => executed,
+                Step::InputNeeded => return Ok(Stop::InputNeeded),
+                Step::Halted => return Ok(Stop::Halted),
+            };
+            if let Some((address, _)) = executed.write {
+                if self.watchpoints.contains(&address) {
+                    return Ok(Stop::Watchpoint(executed));
+                }
+            }
+            match (until, executed.output) {
+                (Until::Step, _) => return Ok(Stop::Stepped(executed)),
+                (Until::Output, Some(value)) => return Ok(Stop::Output(value)),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn step(&mut self) -> Result<Stop, VmError> {
+        self.resume(Until::Step)
+    }
+
+    pub fn cont(&mut self) -> Result<Stop, VmError> {
+        self.resume(Until::Break)
+    }
+
+    pub fn run_until_output(&mut self) -> Result<Stop, VmError> {
+        self.resume(Until::Output)
+    }
+
+    pub fn run_until_input(&mut self) -> Result<Stop, VmError> {
+        self.resume(Until::Input)
+    }
+
+    fn print_state(&self, out: &mut impl Write) -> io::Result<()> {
+        let computer = &self.computer;
+        writeln!(out, "ip: {}", computer.instruction_pointer())?;
+        writeln!(out, "relative base: {}", computer.relative_base())?;
+        writeln!(out, "input: {:?}", computer.input())?;
+        writeln!(out, "output: {:?}", computer.output())
+    }
+
+    fn print_disassembly(
+        &self,
+        out: &mut impl Write,
+        address: usize,
+        count: usize,
+    ) -> io::Result<()> {
+        let memory = self.computer.memory();
+        let mut address = address;
+        for _ in 0..count {
+            if address >= memory.len() {
+                break;
+            }
+            let marker = if address == self.computer.instruction_pointer() {
+                "=>"
+            } else {
+                "  "
+            };
+            let (item, width) = match disasm::decode(memory, address) {
+                Some(instruction) => {
+                    let width = instruction.width();
+                    (disasm::Item::Instruction(instruction), width)
+                }
+                None => (disasm::Item::Data(memory[address]), 1),
+            };
+            writeln!(out, "{} {}", marker, disasm::Line { address, item })?;
+            address += width;
+        }
+        Ok(())
+    }
+
+    /// Runs an interactive session, reading commands from `input` and writing to `out` until
+    /// `quit` or end of input.
+    pub fn repl(&mut self, input: impl BufRead, mut out: impl Write) -> io::Result<()> {
+        for line in input.lines() {
+            let line = line?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            let numbers =
+                || -> Option<Vec<isize>> { words[1..].iter().map(|w| w.parse().ok()).collect() };
+            let result = match words.as_slice() {
+                [] => continue,
+                ["q" | "quit"] => break,
+                ["h" | "help"] => {
+                    writeln!(
+                        out,
+                        "step [n] | continue | output | input | break <addr> | break op <mnemonic>"
+                    )?;
+                    writeln!(
+                        out,
+                        "delete <addr> | watch <addr> | unwatch <addr> | push <values..>"
+                    )?;
+                    writeln!(out, "regs | mem <addr> [n] | dis [addr] [n] | quit")?;
+                    continue;
+                }
+                ["s" | "step"] => self.step(),
+                ["s" | "step", n] => match n.parse::<usize>() {
+                    Ok(n) if n > 0 => self
+                        .step()
+                        .and_then(|first| (1..n).try_fold(first, |_, _| self.step())),
+                    _ => {
+                        writeln!(out, "bad count: {}", n)?;
+                        continue;
+                    }
+                },
+                ["c" | "continue"] => self.cont(),
+                ["o" | "output"] => self.run_until_output(),
+                ["i" | "input"] => self.run_until_input(),
+                ["b" | "break", "op", mnemonic] => {
+                    match Opcode::ALL
+                        .into_iter()
+                        .find(|op| op.mnemonic().eq_ignore_ascii_case(mnemonic))
+                    {
+                        Some(opcode) => {
+                            self.break_on_opcode(opcode);
+                            writeln!(out, "breaking on {}", opcode.mnemonic())?;
+                        }
+                        None => writeln!(out, "unknown opcode: {}", mnemonic)?,
+                    }
+                    continue;
+                }
+                ["b" | "break" | "d" | "delete" | "w" | "watch" | "unwatch", address] => {
+                    match address.parse::<usize>() {
+                        Ok(address) => match words[0] {
+                            "b" | "break" => self.add_breakpoint(address),
+                            "d" | "delete" => {
+                                self.remove_breakpoint(address);
+                            }
+                            "w" | "watch" => self.add_watchpoint(address),
+                            _ => {
+                                self.remove_watchpoint(address);
+                            }
+                        },
+                        Err(_) => writeln!(out, "bad address: {}", address)?,
+                    }
+                    continue;
+                }
+                ["push", ..] => {
+                    match numbers() {
+                        Some(values) => values
+                            .into_iter()
+                            .for_each(|value| self.computer.push_input(value)),
+                        None => writeln!(out, "bad input: {}", line)?,
+                    }
+                    continue;
+                }
+                ["r" | "regs"] => {
+                    self.print_state(&mut out)?;
+                    continue;
+                }
+                ["m" | "mem", ..] | ["dis", ..] => {
+                    // Addresses and counts are never negative, so parse them as `usize` and let
+                    // anything else fall through to "bad arguments".
+                    let arguments: Option<Vec<usize>> =
+                        words[1..].iter().map(|w| w.parse().ok()).collect();
+                    let ip = self.computer.instruction_pointer();
+                    match arguments.as_deref() {
+                        Some(&[]) if words[0] == "dis" => {
+                            self.print_disassembly(&mut out, ip, 5)?
+                        }
+                        Some(&[address]) if words[0] == "dis" => {
+                            self.print_disassembly(&mut out, address, 5)?
+                        }
+                        Some(&[address, count]) if words[0] == "dis" => {
+                            self.print_disassembly(&mut out, address, count)?
+                        }
+                        Some(&[address]) => {
+                            writeln!(out, "[{}] = {}", address, self.computer.read(address))?
+                        }
+                        Some(&[address, count]) => {
+                            let len = self.computer.memory().len();
+                            let end = address.checked_add(count).map_or(len, |end| end.min(len));
+                            let values: Vec<isize> =
+                                (address..end).map(|a| self.computer.read(a)).collect();
+                            writeln!(out, "[{}] = {:?}", address, values)?
+                        }
+                        _ => writeln!(out, "bad arguments: {}", line)?,
+                    }
+                    continue;
+                }
+                _ => {
+                    writeln!(out, "unknown command: {}", line)?;
+                    continue;
+                }
+            };
+            match result {
+                Ok(stop) => writeln!(out, "{}", stop)?,
+                Err(e) => writeln!(out, "error: {}", e)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::fixtures::countdown;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_step() {
+        let mut debugger = Debugger::new(countdown());
+        match debugger.step().unwrap() {
+            Stop::Stepped(executed) => {
+                assert_eq!(executed.ip, 0);
+                assert_eq!(executed.opcode, Opcode::Output);
+                assert_eq!(executed.output, Some(3));
+            }
+            stop => panic!("unexpected {:?}", stop),
+        }
+        assert_eq!(debugger.computer().instruction_pointer(), 2);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let mut debugger = Debugger::new(countdown());
+        debugger.add_breakpoint(6);
+        assert_eq!(debugger.cont(), Ok(Stop::Breakpoint(6)));
+        assert_eq!(debugger.computer().read(12), 2);
+        assert_eq!(debugger.cont(), Ok(Stop::Breakpoint(6)));
+        assert!(debugger.remove_breakpoint(6));
+
+        debugger.break_on_opcode(Opcode::Output);
+        assert_eq!(debugger.cont(), Ok(Stop::OpcodeBreak(Opcode::Output, 0)));
+        assert_eq!(debugger.run_until_output(), Ok(Stop::Output(1)));
+        assert_eq!(debugger.run_until_input(), Ok(Stop::Input(9)));
+        assert_eq!(debugger.cont(), Ok(Stop::InputNeeded));
+        debugger.computer_mut().push_input(5);
+        assert_eq!(debugger.cont(), Ok(Stop::Halted));
+        assert_eq!(debugger.computer().output(), &[3, 2, 1]);
+    }
+
+    #[test]
+    fn test_watchpoints() {
+        let mut debugger = Debugger::new(countdown());
+        debugger.add_watchpoint(12);
+        match debugger.cont().unwrap() {
+            Stop::Watchpoint(executed) => {
+                assert_eq!(executed.ip, 2);
+                assert_eq!(executed.write, Some((12, 2)));
+            }
+            stop => panic!("unexpected {:?}", stop),
+        }
+    }
+
+    #[test]
+    fn test_repl() {
+        let mut debugger = Debugger::new(countdown());
+        let script = "break 6\ncontinue\nregs\nmem 12\nmem 11 1000000000\nmem 9223372036854775807 1\nmem -1\ndis 6 1\nbogus\nstep 0\nstep 2\nquit\nstep\n";
+        let mut out = Vec::new();
+        debugger.repl(Cursor::new(script), &mut out).unwrap();
+        let expected = "\
+        breakpoint at 6\n\
+        ip: 6\n\
+        relative base: 0\n\
+        input: []\n\
+        output: [3]\n\
+        [12] = 2\n\
+        [11] = [99, 2]\n\
+        [9223372036854775807] = []\n\
+        bad arguments: mem -1\n\
+        => 0006: JT [12], #0\n\
+        unknown command: bogus\n\
+        bad count: 0\n\
+        stepped OUT at 0\n";
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,273 @@
+//! Pre-decoded instructions and the fast execution loop behind [`Vm::run`] and
+//! [`IntCode::run_controller`](super::IntCode::run_controller).
+//!
+//! [`Vm::step`] splits the opcode and mode digits out of the instruction word on every
+//! instruction. The fast loop decodes each instruction word once, caches the result by address
+//! and dispatches straight on the cached opcode. A write to an address drops its cache entry,
+//! so self-modifying programs see their changes; operands are never cached, only instruction
+//! words. Anything unusual (faults, strict mode violations, addresses past the dense region)
+//! is handed to [`Vm::step`], so both paths behave identically.
+//!
+//! The cache is shared copy-on-write between forks, so day 19 decodes its program once with
+//! [`Vm::predecode`] and then forks a fresh VM for every point.
+
+use std::sync::Arc;
+
+use super::{word::Word, Mode, Opcode, Step, Vm, VmError};
+
+/// An instruction word split into its opcode and parameter modes. Modes for parameters the
+/// opcode doesn't have are [`Mode::Position`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Decoded {
+    pub opcode: Opcode,
+    pub modes: [Mode; 3],
+}
+
+impl Decoded {
+    /// Decodes an instruction word, or returns `None` if it has an unknown opcode, an unknown
+    /// mode, or an immediate mode write parameter.
+    pub fn new(instruction: isize) -> Option<Decoded> {
+        let opcode = Opcode::from_code(instruction % 100)?;
+        let mut modes = [Mode::Position; 3];
+        for (pos, mode) in modes.iter_mut().enumerate().take(opcode.parameter_count()) {
+            *mode = Mode::from_digit(Mode::digit_of(instruction, pos + 1))?;
+        }
+        match opcode.write_parameter() {
+            Some(pos) if modes[pos - 1] == Mode::Immediate => None,
+            _ => Some(Decoded { opcode, modes }),
+        }
+    }
+}
+
+/// Decoded instructions by address, covering the dense region of memory.
+#[derive(Debug, Clone, Default)]
+pub(super) struct Cache {
+    entries: Arc<Vec<Option<Decoded>>>,
+}
+
+impl Cache {
+    fn get(&self, address: usize) -> Option<Decoded> {
+        self.entries.get(address).copied().flatten()
+    }
+
+    fn insert(&mut self, address: usize, decoded: Decoded, len: usize) {
+        let entries = Arc::make_mut(&mut self.entries);
+        if entries.len() < len {
+            entries.resize(len, None);
+        }
+        entries[address] = Some(decoded);
+    }
+
+    /// Forgets the instruction at `address` after it has been written to.
+    pub(super) fn invalidate(&mut self, address: usize) {
+        if self.get(address).is_some() {
+            Arc::make_mut(&mut self.entries)[address] = None;
+        }
+    }
+}
+
+/// Why [`Vm::run_cached`] returned.
+pub(super) enum Stop {
+    /// An output instruction ran. The value is at the end of the output buffer.
+    Output,
+    InputNeeded,
+    Halted,
+}
+
+impl<W: Word> Vm<W> {
+    /// Decodes every instruction in the dense region up front. Words that don't decode are
+    /// left for the slow path. Data words that happen to look like instructions are harmless:
+    /// they are only used if execution reaches them.
+    pub fn predecode(&mut self) {
+        for address in 0..self.program.dense().len() {
+            if self.cache.get(address).is_none() {
+                self.decode(address);
+            }
+        }
+    }
+
+    /// Decodes and caches the instruction at `ip`, if the fast loop can run it.
+    fn decode(&mut self, ip: usize) -> Option<Decoded> {
+        let len = self.program.dense().len();
+        if ip >= len {
+            return None;
+        }
+        let code = self.read(ip).to_isize()?;
+        let decoded = Decoded::new(code)?;
+        let count = decoded.opcode.parameter_count();
+        if self.strict && (ip + count >= len || code / 10_isize.pow(count as u32 + 2) != 0) {
+            return None;
+        }
+        self.cache.insert(ip, decoded, len);
+        Some(decoded)
+    }
+
+    /// The address named by parameter `pos`, or `None` if it isn't a valid address.
+    fn address(&self, ip: usize, mode: Mode, pos: usize) -> Option<usize> {
+        match mode {
+            Mode::Position => self.read(ip + pos).to_usize(),
+            Mode::Immediate => Some(ip + pos),
+            Mode::Relative => self
+                .relative_base
+                .checked_add(&self.read(ip + pos))?
+                .to_usize(),
+        }
+    }
+
+    fn operand(&self, ip: usize, decoded: &Decoded, pos: usize) -> Option<W> {
+        let address = self.address(ip, decoded.modes[pos - 1], pos)?;
+        if self.strict && address >= self.program.len() {
+            return None;
+        }
+        Some(self.read(address))
+    }
+
+    /// Executes the instruction at the instruction pointer without any of the checks that
+    /// [`Vm::step`] reports as errors. Returns `None`, having changed nothing, if one of them
+    /// would fail.
+    fn execute(&mut self, decoded: &Decoded) -> Option<Option<Stop>> {
+        let ip = self.index;
+        match decoded.opcode {
+            Opcode::Add | Opcode::Multiply | Opcode::LessThan | Opcode::Equals => {
+                let input1 = self.operand(ip, decoded, 1)?;
+                let input2 = self.operand(ip, decoded, 2)?;
+                let target = self.address(ip, decoded.modes[2], 3)?;
+                let result = match decoded.opcode {
+                    Opcode::Add => self.add(ip, &input1, &input2).ok()?,
+                    Opcode::Multiply => self.multiply(ip, &input1, &input2).ok()?,
+                    Opcode::LessThan if input1 < input2 => W::one(),
+                    Opcode::Equals if input1 == input2 => W::one(),
+                    _ => W::zero(),
+                };
+                self.write(target, result).ok()?;
+                self.index += 4;
+            }
+            Opcode::Input => {
+                let target = self.address(ip, decoded.modes[0], 1)?;
+                let data = match self.input.front() {
+                    Some(data) => data.clone(),
+                    None => return Some(Some(Stop::InputNeeded)),
+                };
+                self.write(target, data).ok()?;
+                self.input.pop_front();
+                self.index += 2;
+            }
+            Opcode::Output => {
+                let value = self.operand(ip, decoded, 1)?;
+                self.output.push(value);
+                self.index += 2;
+                return Some(Some(Stop::Output));
+            }
+            Opcode::JumpIfTrue | Opcode::JumpIfFalse => {
+                let input1 = self.operand(ip, decoded, 1)?;
+                let input2 = self.operand(ip, decoded, 2)?;
+                if input1.is_zero() == (decoded.opcode == Opcode::JumpIfFalse) {
+                    match input2.to_usize() {
+                        Some(target)
+                            if target < self.program.dense().len()
+                                || target < self.program.len() =>
+                        {
+                            self.index = target
+                        }
+                        _ => return None,
+                    }
+                } else {
+                    self.index += 3;
+                }
+            }
+            Opcode::AdjustRelativeBase => {
+                let input1 = self.operand(ip, decoded, 1)?;
+                self.relative_base = self.add(ip, &self.relative_base, &input1).ok()?;
+                self.index += 2;
+            }
+            Opcode::Halt => return Some(Some(Stop::Halted)),
+        }
+        Some(None)
+    }
+
+    /// Runs until the program halts, needs input or, if `stop_on_output` is set, produces an
+    /// output.
+    pub(super) fn run_cached(&mut self, stop_on_output: bool) -> Result<Stop, VmError<W>> {
+        loop {
+            let decoded = match self.cache.get(self.index) {
+                Some(decoded) => Some(decoded),
+                None => self.decode(self.index),
+            };
+            if let Some(stop) = decoded.and_then(|decoded| self.execute(&decoded)) {
+                match stop {
+                    Some(Stop::Output) if !stop_on_output => {}
+                    Some(stop) => return Ok(stop),
+                    None => {}
+                }
+                continue;
+            }
+            match self.step()? {
+                Step::Executed(executed) => {
+                    if stop_on_output && executed.output.is_some() {
+                        return Ok(Stop::Output);
+                    }
+                }
+                Step::InputNeeded => return Ok(Stop::InputNeeded),
+                Step::Halted => return Ok(Stop::Halted),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{IntCode, IntCodeState};
+
+    #[test]
+    fn test_decode() {
+        let decoded = Decoded::new(21102).unwrap();
+        assert_eq!(decoded.opcode, Opcode::Multiply);
+        assert_eq!(
+            decoded.modes,
+            [Mode::Immediate, Mode::Immediate, Mode::Relative]
+        );
+        assert_eq!(Decoded::new(104).unwrap().modes[0], Mode::Immediate);
+        assert_eq!(Decoded::new(42), None);
+        assert_eq!(Decoded::new(301), None);
+        assert_eq!(Decoded::new(11101), None);
+        assert_eq!(Decoded::new(103), None);
+    }
+
+    #[test]
+    fn test_self_modifying() {
+        // Outputs #20, then patches its first instruction from OUT #20 to OUT [20] and loops
+        // back once, outputting the 42 stored at address 20.
+        let program = vec![
+            104, 20, // OUT #20
+            1005, 21, 16, // JT [21] 16
+            1101, 0, 4, 0, // ADD #0 #4 [0]
+            1101, 0, 1, 21, // ADD #0 #1 [21]
+            1105, 1, 0, // JT #1 0
+            99, 0, 0, 0, // HLT
+            42, 0,
+        ];
+        let mut computer = IntCode::new(program.clone(), vec![]);
+        computer.predecode();
+        assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![20, 42])));
+
+        let mut stepped = IntCode::new(program, vec![]);
+        assert_eq!(stepped.run_traced(0, &mut ()), computer.run(0));
+    }
+
+    #[test]
+    fn test_forks_share_cache() {
+        let mut template = IntCode::new(vec![3, 9, 1002, 9, 2, 9, 4, 9, 99, 0], vec![]);
+        template.predecode();
+        for value in 1..4 {
+            let mut computer = template.fork();
+            computer.push_input(value);
+            assert_eq!(computer.run(0), Ok(IntCodeState::Halted(vec![value * 2])));
+            // Writing to data leaves the cached instructions alone.
+            assert!(Arc::ptr_eq(
+                &template.cache.entries,
+                &computer.cache.entries
+            ));
+        }
+    }
+}
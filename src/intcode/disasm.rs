@@ -0,0 +1,199 @@
+//! Turns IntCode memory back into readable assembly.
+//!
+//! Operands are written as `[12]` (position), `#5` (immediate) and `rb+3` (relative). Words
+//! that do not decode to a valid instruction are shown as `DATA`.
+
+use std::fmt::Display;
+
+use super::{Mode, Opcode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operand {
+    pub mode: Mode,
+    pub value: isize,
+}
+
+impl Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.mode {
+            Mode::Position => write!(f, "[{}]", self.value),
+            Mode::Immediate => write!(f, "#{}", self.value),
+            Mode::Relative if self.value < 0 => write!(f, "rb{}", self.value),
+            Mode::Relative => write!(f, "rb+{}", self.value),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Instruction {
+    pub opcode: Opcode,
+    pub operands: Vec<Operand>,
+}
+
+impl Instruction {
+    /// Number of memory words the instruction occupies.
+    pub fn width(&self) -> usize {
+        self.operands.len() + 1
+    }
+
+    /// Encodes the instruction back into memory words.
+    pub fn encode(&self) -> Vec<isize> {
+        let instruction = self
+            .operands
+            .iter()
+            .enumerate()
+            .fold(self.opcode.code(), |word, (i, operand)| {
+                word + operand.mode.digit() * 10_isize.pow(i as u32 + 2)
+            });
+        std::iter::once(instruction)
+            .chain(self.operands.iter().map(|operand| operand.value))
+            .collect()
+    }
+}
+
+impl Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.opcode.mnemonic())?;
+        for (i, operand) in self.operands.iter().enumerate() {
+            let separator = if i == 0 { " " } else { ", " };
+            write!(f, "{}{}", separator, operand)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    Instruction(Instruction),
+    Data(isize),
+}
+
+/// One line of a listing: an instruction or data word at `address`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Line {
+    pub address: usize,
+    pub item: Item,
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.item {
+            Item::Instruction(instruction) => write!(f, "{:04}: {}", self.address, instruction),
+            Item::Data(value) => write!(f, "{:04}: DATA {}", self.address, value),
+        }
+    }
+}
+
+/// Decodes the instruction at `address`. Returns `None` if the word is not a valid opcode,
+/// has unknown or unused mode digits, writes through an immediate operand, or runs past the
+/// end of memory.
+pub fn decode(memory: &[isize], address: usize) -> Option<Instruction> {
+    let instruction = *memory.get(address)?;
+    if instruction < 0 {
+        return None;
+    }
+    let opcode = Opcode::from_code(instruction % 100)?;
+    let count = opcode.parameter_count();
+    if instruction / 10_isize.pow(count as u32 + 2) != 0 {
+        return None;
+    }
+    let operands = (1..=count)
+        .map(|pos| {
+            let mode = Mode::from_digit(Mode::digit_of(instruction, pos))?;
+            if mode == Mode::Immediate && opcode.write_parameter() == Some(pos) {
+                return None;
+            }
+            let value = *memory.get(address + pos)?;
+            Some(Operand { mode, value })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(Instruction { opcode, operands })
+}
+
+/// Disassembles memory with a linear sweep, falling back to `DATA` one word at a time.
+pub fn disassemble(memory: &[isize]) -> Vec<Line> {
+    let mut lines = Vec::new();
+    let mut address = 0;
+    while address < memory.len() {
+        match decode(memory, address) {
+            Some(instruction) => {
+                let width = instruction.width();
+                lines.push(Line {
+                    address,
+                    item: Item::Instruction(instruction),
+                });
+                address += width;
+            }
+            None => {
+                lines.push(Line {
+                    address,
+                    item: Item::Data(memory[address]),
+                });
+                address += 1;
+            }
+        }
+    }
+    lines
+}
+
+/// The disassembly as text, one line per instruction.
+pub fn listing(memory: &[isize]) -> String {
+    disassemble(memory)
+        .iter()
+        .map(|line| line.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_listing() {
+        let program = vec![1002, 4, 3, 4, 33];
+        assert_eq!(listing(&program), "0000: MUL [4], #3, [4]\n0004: DATA 33");
+
+        let program = vec![109, 19, 204, -34, 21101, 5, 6, 3, 99];
+        let expected = "\
+        0000: ARB #19\n\
+        0002: OUT rb-34\n\
+        0004: ADD #5, #6, rb+3\n\
+        0008: HLT";
+        assert_eq!(listing(&program), expected);
+    }
+
+    #[test]
+    fn test_data_fallback() {
+        // Bad opcode, bad mode, immediate write target, unused mode digit and truncated operands.
+        let program = vec![42, 304, 11101, 10099, 1, 0];
+        let items: Vec<Item> = disassemble(&program)
+            .into_iter()
+            .map(|line| line.item)
+            .collect();
+        assert_eq!(
+            items,
+            vec![
+                Item::Data(42),
+                Item::Data(304),
+                Item::Data(11101),
+                Item::Data(10099),
+                Item::Data(1),
+                Item::Data(0)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_encode() {
+        let program = vec![3, 9, 8, 9, 10, 9, 4, 9, 99, -1, 8, 1105, 1, 0];
+        let encoded: Vec<isize> = disassemble(&program)
+            .into_iter()
+            .flat_map(|line| match line.item {
+                Item::Instruction(instruction) => instruction.encode(),
+                Item::Data(value) => vec![value],
+            })
+            .collect();
+        assert_eq!(encoded, program);
+    }
+}
@@ -0,0 +1,18 @@
+//! Programs shared by the tests of the debugging and tracing tools.
+
+use super::{asm::assemble, IntCode};
+
+/// Outputs 3, 2 and 1, then reads a value into the counter and halts. The loop starts at 0,
+/// the `JT` back to it is at 6 and the counter lives at 12.
+pub fn countdown() -> IntCode {
+    let program = assemble(
+        "start:  OUT [counter]
+                 ADD [counter], #-1, [counter]
+                 JT [counter], #start
+                 IN [counter]
+                 HLT
+        counter: DATA 3",
+    )
+    .unwrap();
+    IntCode::new(program, vec![])
+}
@@ -0,0 +1,264 @@
+//! Pluggable input and output for [`IntCode::run_io`](super::IntCode::run_io) and
+//! [`IntCode::run_controller`](super::IntCode::run_controller).
+//!
+//! Sources and sinks can be closures, queues, channels, iterators, ASCII text or the process's
+//! stdin/stdout, so a VM can be wired straight to another VM or to a controller object without
+//! polling for a fixed number of outputs.
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, Write},
+    sync::mpsc::{Receiver, Sender},
+};
+
+/// A source of input values. Returning `None` pauses the VM with
+/// [`IntCodeState::InputNeeded`](super::IntCodeState::InputNeeded).
+pub trait Input {
+    fn next_input(&mut self) -> Option<isize>;
+}
+
+/// A sink for output values.
+pub trait Output {
+    fn output(&mut self, value: isize);
+}
+
+/// An object that both feeds and consumes a VM, such as a game or robot controller.
+pub trait Controller {
+    fn input(&mut self) -> Option<isize>;
+    fn output(&mut self, value: isize);
+}
+
+impl<F: FnMut() -> Option<isize>> Input for F {
+    fn next_input(&mut self) -> Option<isize> {
+        self()
+    }
+}
+
+impl<F: FnMut(isize)> Output for F {
+    fn output(&mut self, value: isize) {
+        self(value)
+    }
+}
+
+impl Input for VecDeque<isize> {
+    fn next_input(&mut self) -> Option<isize> {
+        self.pop_front()
+    }
+}
+
+impl Output for VecDeque<isize> {
+    fn output(&mut self, value: isize) {
+        self.push_back(value)
+    }
+}
+
+impl Output for Vec<isize> {
+    fn output(&mut self, value: isize) {
+        self.push(value)
+    }
+}
+
+/// Blocks until a value arrives, returning `None` once every sender has gone.
+impl Input for Receiver<isize> {
+    fn next_input(&mut self) -> Option<isize> {
+        self.recv().ok()
+    }
+}
+
+/// Sends each value; values sent after the receiver has gone are dropped.
+impl Output for Sender<isize> {
+    fn output(&mut self, value: isize) {
+        let _ = self.send(value);
+    }
+}
+
+/// Input from any iterator of values.
+#[derive(Debug, Clone)]
+pub struct IterInput<I>(pub I);
+
+impl<I: Iterator<Item = isize>> Input for IterInput<I> {
+    fn next_input(&mut self) -> Option<isize> {
+        self.0.next()
+    }
+}
+
+/// Feeds text to the VM one character code at a time.
+#[derive(Debug, Clone, Default)]
+pub struct AsciiInput {
+    buffer: VecDeque<isize>,
+}
+
+impl AsciiInput {
+    pub fn new(text: &str) -> AsciiInput {
+        let mut input = AsciiInput::default();
+        input.push_str(text);
+        input
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.buffer.extend(text.chars().map(|c| c as isize));
+    }
+}
+
+impl Input for AsciiInput {
+    fn next_input(&mut self) -> Option<isize> {
+        self.buffer.pop_front()
+    }
+}
+
+/// Connects a VM to the terminal: input is read from stdin a line at a time and ASCII output is
+/// written to stdout. Values outside the ASCII range are printed as numbers on their own line.
+#[derive(Debug, Default)]
+pub struct Stdio {
+    pending: AsciiInput,
+}
+
+impl Input for Stdio {
+    fn next_input(&mut self) -> Option<isize> {
+        if self.pending.buffer.is_empty() {
+            let mut line = String::new();
+            match io::stdin().lock().read_line(&mut line) {
+                Ok(0) | Err(_) => return None,
+                Ok(_) => self.pending.push_str(&line),
+            }
+        }
+        self.pending.next_input()
+    }
+}
+
+impl Output for Stdio {
+    fn output(&mut self, value: isize) {
+        let mut stdout = io::stdout().lock();
+        let _ = match u8::try_from(value) {
+            Ok(c) if c.is_ascii() => write!(stdout, "{}", c as char),
+            _ => writeln!(stdout, "{}", value),
+        };
+    }
+}
+
+/// Pairs a separate input and output into a [`Controller`].
+pub(super) struct Wired<'a, I, O> {
+    pub(super) input: &'a mut I,
+    pub(super) output: &'a mut O,
+}
+
+impl<I: Input, O: Output> Controller for Wired<'_, I, O> {
+    fn input(&mut self) -> Option<isize> {
+        self.input.next_input()
+    }
+
+    fn output(&mut self, value: isize) {
+        self.output.output(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{asm::assemble, IntCode, IntCodeState};
+    use std::{sync::mpsc::channel, thread};
+
+    /// Outputs twice each input until it reads 0.
+    fn doubler() -> IntCode {
+        let program = assemble(
+            "start: IN [x]
+                    JF [x], #end
+                    MUL [x], #2, [x]
+                    OUT [x]
+                    JT #1, #start
+             end:   HLT
+             x:     DATA 0",
+        )
+        .unwrap();
+        IntCode::new(program, vec![])
+    }
+
+    #[test]
+    fn test_queues() {
+        let mut input = VecDeque::from(vec![1, 2, 3]);
+        let mut output = Vec::new();
+        let state = doubler().run_io(&mut input, &mut output).unwrap();
+        assert_eq!(state, IntCodeState::InputNeeded);
+        assert_eq!(output, vec![2, 4, 6]);
+
+        let mut output = Vec::new();
+        let state = doubler()
+            .run_io(&mut IterInput([5, 0].into_iter()), &mut output)
+            .unwrap();
+        assert_eq!(state, IntCodeState::Halted(vec![]));
+        assert_eq!(output, vec![10]);
+    }
+
+    #[test]
+    fn test_closures() {
+        let mut n = 0;
+        let mut total = 0;
+        doubler()
+            .run_io(
+                &mut || {
+                    n += 1;
+                    (n <= 4).then_some(n)
+                },
+                &mut |value| total += value,
+            )
+            .unwrap();
+        assert_eq!(total, 20);
+    }
+
+    #[test]
+    fn test_ascii() {
+        let mut output = Vec::new();
+        doubler()
+            .run_io(&mut AsciiInput::new("ab"), &mut output)
+            .unwrap();
+        assert_eq!(output, vec![194, 196]);
+    }
+
+    #[test]
+    fn test_controller() {
+        struct Counter {
+            next: isize,
+            seen: Vec<isize>,
+        }
+        impl Controller for Counter {
+            fn input(&mut self) -> Option<isize> {
+                self.next -= 1;
+                Some(self.next)
+            }
+            fn output(&mut self, value: isize) {
+                self.seen.push(value);
+            }
+        }
+        let mut counter = Counter {
+            next: 4,
+            seen: vec![],
+        };
+        let state = doubler().run_controller(&mut counter).unwrap();
+        assert_eq!(state, IntCodeState::Halted(vec![]));
+        assert_eq!(counter.seen, vec![6, 4, 2]);
+    }
+
+    #[test]
+    fn test_channels() {
+        // Chain two doublers together across threads.
+        let (to_first, mut first_input) = channel();
+        let (mut first_output, mut second_input) = channel();
+        let (mut second_output, results) = channel();
+        let first = thread::spawn(move || {
+            doubler()
+                .run_io(&mut first_input, &mut first_output)
+                .unwrap()
+        });
+        let second = thread::spawn(move || {
+            doubler()
+                .run_io(&mut second_input, &mut second_output)
+                .unwrap()
+        });
+        for value in [1, 2, 3, 0] {
+            to_first.send(value).unwrap();
+        }
+        assert_eq!(first.join().unwrap(), IntCodeState::Halted(vec![]));
+        assert_eq!(second.join().unwrap(), IntCodeState::InputNeeded);
+        assert_eq!(results.iter().collect::<Vec<_>>(), vec![4, 8, 12]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Sparse IntCode memory.
+//!
+//! The program and any memory written just past it live in a dense vector. Writes far beyond
+//! that allocate fixed-size pages on demand, so a single large address costs one page rather
+//! than a vector stretching all the way out to it.
+//!
+//! Both the dense region and the pages are shared between clones and copied on first write,
+//! so cloning a VM to explore several branches of a search is cheap.
+
+use std::{collections::HashMap, sync::Arc};
+
+use super::word::Word;
+
+/// Number of words in a page, and how far past the dense region a write may land before it
+/// goes to a page instead of growing the dense region.
+pub const PAGE_SIZE: usize = 1024;
+
+/// A write would take the allocated memory past the configured limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LimitExceeded;
+
+#[derive(Debug, Clone, Default)]
+pub struct Memory<W = isize> {
+    dense: Arc<Vec<W>>,
+    pages: HashMap<usize, Arc<[W; PAGE_SIZE]>>,
+    limit: Option<usize>,
+}
+
+impl<W: Word> Memory<W> {
+    pub fn new(program: Vec<W>) -> Memory<W> {
+        Memory {
+            dense: Arc::new(program),
+            pages: HashMap::new(),
+            limit: None,
+        }
+    }
+
+    /// Rebuilds memory from a dense region and `(start address, words)` pages as returned by
+    /// [`pages`](Memory::pages). Returns `None` if a page is misaligned, the wrong size or
+    /// overlaps the dense region.
+    pub fn from_parts(
+        dense: Vec<W>,
+        pages: Vec<(usize, Vec<W>)>,
+        limit: Option<usize>,
+    ) -> Option<Memory<W>> {
+        let mut memory = Memory::new(dense).with_limit(limit);
+        for (start, words) in pages {
+            if start % PAGE_SIZE != 0 || start < memory.dense.len() {
+                return None;
+            }
+            let words: [W; PAGE_SIZE] = words.try_into().ok()?;
+            memory.pages.insert(start / PAGE_SIZE, Arc::new(words));
+        }
+        Some(memory)
+    }
+
+    /// Caps the number of words that may be allocated. The initial program always fits.
+    pub fn with_limit(mut self, limit: Option<usize>) -> Memory<W> {
+        self.limit = limit;
+        self
+    }
+
+    pub fn read(&self, address: usize) -> W {
+        match self.dense.get(address) {
+            Some(value) => value.clone(),
+            None => self
+                .pages
+                .get(&(address / PAGE_SIZE))
+                .map_or_else(W::zero, |page| page[address % PAGE_SIZE].clone()),
+        }
+    }
+
+    pub fn write(&mut self, address: usize, value: W) -> Result<(), LimitExceeded> {
+        if address < self.dense.len() {
+            Arc::make_mut(&mut self.dense)[address] = value;
+            return Ok(());
+        }
+        let page = address / PAGE_SIZE;
+        if let Some(page) = self.pages.get_mut(&page) {
+            Arc::make_mut(page)[address % PAGE_SIZE] = value;
+            return Ok(());
+        }
+        if address < self.dense.len() + PAGE_SIZE {
+            self.check_limit(address + 1 - self.dense.len())?;
+            let dense = Arc::make_mut(&mut self.dense);
+            dense.resize(address + 1, W::zero());
+            dense[address] = value;
+        } else {
+            self.check_limit(PAGE_SIZE)?;
+            let mut new_page: [W; PAGE_SIZE] = std::array::from_fn(|_| W::zero());
+            new_page[address % PAGE_SIZE] = value;
+            self.pages.insert(page, Arc::new(new_page));
+        }
+        Ok(())
+    }
+
+    fn check_limit(&self, extra: usize) -> Result<(), LimitExceeded> {
+        match self.limit {
+            Some(limit) if self.allocated() + extra > limit => Err(LimitExceeded),
+            _ => Ok(()),
+        }
+    }
+
+    /// Number of words currently allocated.
+    pub fn allocated(&self) -> usize {
+        self.dense.len() + self.pages.len() * PAGE_SIZE
+    }
+
+    /// One past the highest address that has been allocated.
+    pub fn len(&self) -> usize {
+        self.pages
+            .keys()
+            .map(|page| (page + 1) * PAGE_SIZE)
+            .max()
+            .unwrap_or(0)
+            .max(self.dense.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The dense region: the program and anything written contiguously after it.
+    pub fn dense(&self) -> &[W] {
+        &self.dense
+    }
+
+    pub fn limit(&self) -> Option<usize> {
+        self.limit
+    }
+
+    /// The allocated pages beyond the dense region as `(start address, words)`, in address
+    /// order.
+    pub fn pages(&self) -> Vec<(usize, &[W])> {
+        let mut pages: Vec<_> = self
+            .pages
+            .iter()
+            .map(|(&page, words)| (page * PAGE_SIZE, &words[..]))
+            .collect();
+        pages.sort_by_key(|&(start, _)| start);
+        pages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dense_growth() {
+        let mut memory: Memory = Memory::new(vec![1, 2, 3]);
+        memory.write(5, 6).unwrap();
+        assert_eq!(memory.dense(), &[1, 2, 3, 0, 0, 6]);
+        assert_eq!(memory.read(5), 6);
+        assert_eq!(memory.read(500), 0);
+    }
+
+    #[test]
+    fn test_pages() {
+        let mut memory: Memory = Memory::new(vec![1, 2, 3]);
+        memory.write(1 << 40, 7).unwrap();
+        assert_eq!(memory.dense(), &[1, 2, 3]);
+        assert_eq!(memory.read(1 << 40), 7);
+        assert_eq!(memory.read((1 << 40) + 1), 0);
+        assert_eq!(memory.allocated(), 3 + PAGE_SIZE);
+        assert_eq!(memory.len(), (1 << 40) + PAGE_SIZE);
+    }
+
+    #[test]
+    fn test_limit() {
+        let mut memory: Memory = Memory::new(vec![1, 2, 3]).with_limit(Some(10));
+        assert_eq!(memory.write(9, 1), Ok(()));
+        assert_eq!(memory.write(10, 1), Err(LimitExceeded));
+        assert_eq!(memory.write(100_000, 1), Err(LimitExceeded));
+        assert_eq!(memory.read(9), 1);
+    }
+
+    #[test]
+    fn test_copy_on_write() {
+        let mut memory: Memory = Memory::new(vec![1, 2, 3]);
+        memory.write(1 << 20, 4).unwrap();
+        let mut fork = memory.clone();
+        fork.write(0, 10).unwrap();
+        fork.write(1 << 20, 40).unwrap();
+        assert_eq!(memory.dense(), &[1, 2, 3]);
+        assert_eq!(memory.read(1 << 20), 4);
+        assert_eq!(fork.dense(), &[10, 2, 3]);
+        assert_eq!(fork.read(1 << 20), 40);
+    }
+}
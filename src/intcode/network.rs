@@ -0,0 +1,561 @@
+//! Runs several IntCode VMs as a network that passes messages between them (days 7 and 23).
+//!
+//! [`Network::run`] schedules the nodes round-robin on the current thread, so a run is always
+//! deterministic. [`Network::run_threaded`] gives every node its own thread instead. Both detect
+//! when the whole network has gone idle and report every message and idle period to a
+//! [`Monitor`], which is where a NAT or a logger hooks in.
+
+use std::{
+    collections::VecDeque,
+    error::Error,
+    fmt::Display,
+    sync::mpsc::{channel, Sender, TryRecvError},
+    thread,
+};
+
+use super::{IntCode, IntCodeState, VmError};
+
+/// How a node's output is split into messages and where they go.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Links {
+    /// Every value node `i` outputs is sent on its own to node `targets[i]`.
+    Direct(Vec<usize>),
+    /// Nodes output packets of `len` words. The first word is the destination address and the
+    /// rest is delivered.
+    Packets { len: usize },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event<'a> {
+    /// A node sent a message. Messages to an address with no node behind it, such as the NAT
+    /// in day 23, are only seen here.
+    Message {
+        from: usize,
+        to: isize,
+        values: &'a [isize],
+    },
+    /// Every running node is waiting for input and every message has been read.
+    Idle,
+}
+
+/// What the network should do after an [`Event`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    Continue,
+    /// Delivers extra values to a node. Values for a node that doesn't exist are dropped.
+    Send(usize, Vec<isize>),
+    Stop,
+}
+
+/// Watches the network and may inject messages or stop it.
+pub trait Monitor {
+    fn event(&mut self, event: Event<'_>) -> Action;
+}
+
+/// Lets the network run until it halts or goes idle.
+impl Monitor for () {
+    fn event(&mut self, _event: Event<'_>) -> Action {
+        Action::Continue
+    }
+}
+
+impl<F: FnMut(Event<'_>) -> Action> Monitor for F {
+    fn event(&mut self, event: Event<'_>) -> Action {
+        self(event)
+    }
+}
+
+/// Why a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// Every node halted.
+    Halted,
+    /// The network went idle and nothing woke it up.
+    Idle,
+    /// The monitor returned [`Action::Stop`].
+    Stopped,
+}
+
+/// A VM error, along with the node it happened on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeError {
+    pub node: usize,
+    pub error: VmError,
+}
+
+impl Display for NodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node {}: {}", self.node, self.error)
+    }
+}
+
+impl Error for NodeError {}
+
+#[derive(Debug, Clone)]
+pub struct Network {
+    nodes: Vec<IntCode>,
+    inboxes: Vec<VecDeque<isize>>,
+    pending: Vec<Vec<isize>>,
+    sent: Vec<Vec<isize>>,
+    halted: Vec<bool>,
+    links: Links,
+    empty_input: Option<isize>,
+}
+
+enum Report {
+    Output(usize, isize),
+    /// The node found its inbox empty after reading this many values.
+    Waiting(usize, usize),
+    Stopped(usize, Result<IntCodeState, VmError>),
+}
+
+/// The coordinator's end of a node running on another thread.
+struct Remote {
+    input: Sender<isize>,
+    delivered: usize,
+    waiting: bool,
+}
+
+impl Remote {
+    fn deliver(&mut self, values: Vec<isize>) {
+        self.delivered += values.len();
+        self.waiting = false;
+        for value in values {
+            let _ = self.input.send(value);
+        }
+    }
+}
+
+impl Network {
+    pub fn new(nodes: Vec<IntCode>, links: Links) -> Network {
+        let n = nodes.len();
+        Network {
+            nodes,
+            inboxes: vec![VecDeque::new(); n],
+            pending: vec![Vec::new(); n],
+            sent: vec![Vec::new(); n],
+            halted: vec![false; n],
+            links,
+            empty_input: None,
+        }
+    }
+
+    /// The value a node reads when its inbox is empty, for programs that poll for input rather
+    /// than block on it (day 23 uses -1).
+    pub fn with_empty_input(mut self, value: isize) -> Network {
+        self.empty_input = Some(value);
+        self
+    }
+
+    /// Queues values for a node to read. Returns `false`, and drops the values, if there is no
+    /// such node.
+    pub fn send(&mut self, node: usize, values: &[isize]) -> bool {
+        match self.inboxes.get_mut(node) {
+            Some(inbox) => {
+                inbox.extend(values);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        // Not `nodes`, which is empty while the nodes are out on their threads.
+        self.inboxes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn node(&self, node: usize) -> &IntCode {
+        &self.nodes[node]
+    }
+
+    /// Everything a node has output so far, including packet addresses.
+    pub fn outputs(&self, node: usize) -> &[isize] {
+        &self.sent[node]
+    }
+
+    pub fn is_halted(&self, node: usize) -> bool {
+        self.halted[node]
+    }
+
+    /// Records an output value, returning the message it completes, if any.
+    fn collect(&mut self, from: usize, value: isize) -> Option<(isize, Vec<isize>)> {
+        self.sent[from].push(value);
+        match &self.links {
+            Links::Direct(targets) => Some((targets[from] as isize, vec![value])),
+            Links::Packets { len } => {
+                self.pending[from].push(value);
+                if self.pending[from].len() < *len {
+                    return None;
+                }
+                let mut packet = std::mem::take(&mut self.pending[from]);
+                let to = packet.remove(0);
+                Some((to, packet))
+            }
+        }
+    }
+
+    /// Reports a message to the monitor and works out what to deliver. Returns `None` if the
+    /// monitor asked to stop.
+    fn dispatch(
+        &self,
+        from: usize,
+        to: isize,
+        values: Vec<isize>,
+        monitor: &mut impl Monitor,
+    ) -> Option<Vec<(usize, Vec<isize>)>> {
+        let event = Event::Message {
+            from,
+            to,
+            values: &values,
+        };
+        let extra = match monitor.event(event) {
+            Action::Continue => None,
+            Action::Send(node, values) => Some((node, values)),
+            Action::Stop => return None,
+        };
+        let mut deliveries = Vec::new();
+        if let Ok(to) = usize::try_from(to) {
+            if to < self.len() {
+                deliveries.push((to, values));
+            }
+        }
+        deliveries.extend(extra.filter(|&(node, _)| node < self.len()));
+        Some(deliveries)
+    }
+
+    /// Runs every node in turn until each is waiting for input, and repeats until the network
+    /// halts, goes idle or is stopped by the monitor. Runs are deterministic.
+    pub fn run(&mut self, monitor: &mut impl Monitor) -> Result<Outcome, NodeError> {
+        loop {
+            let mut active = false;
+            for i in 0..self.len() {
+                if self.halted[i] {
+                    continue;
+                }
+                match (self.inboxes[i].is_empty(), self.empty_input) {
+                    (true, Some(value)) => self.inboxes[i].push_back(value),
+                    (true, None) => {}
+                    (false, _) => active = true,
+                }
+                let mut output = Vec::new();
+                let state = self.nodes[i]
+                    .run_io(&mut self.inboxes[i], &mut output)
+                    .map_err(|error| NodeError { node: i, error })?;
+                if let IntCodeState::Halted(_) = state {
+                    self.halted[i] = true;
+                    active = true;
+                }
+                for value in output {
+                    active = true;
+                    if let Some((to, values)) = self.collect(i, value) {
+                        match self.dispatch(i, to, values, monitor) {
+                            Some(deliveries) => {
+                                for (node, values) in deliveries {
+                                    self.send(node, &values);
+                                }
+                            }
+                            None => return Ok(Outcome::Stopped),
+                        }
+                    }
+                }
+            }
+            if self.halted.iter().all(|&halted| halted) {
+                return Ok(Outcome::Halted);
+            }
+            if !active {
+                match monitor.event(Event::Idle) {
+                    Action::Send(node, values) if node < self.len() => {
+                        self.send(node, &values);
+                    }
+                    // Polling nodes may still do something on their own. A message to a node
+                    // that doesn't exist wakes nothing up.
+                    Action::Send(..) | Action::Continue if self.empty_input.is_some() => {}
+                    Action::Send(..) | Action::Continue => return Ok(Outcome::Idle),
+                    Action::Stop => return Ok(Outcome::Stopped),
+                }
+            }
+        }
+    }
+
+    /// Like [`run`](Network::run), but with every node on its own thread. Messages are routed
+    /// and the monitor is called on the current thread, in whatever order the nodes produce
+    /// them.
+    ///
+    /// Nodes are shut down by closing their input when the run ends, so a node that never
+    /// reads input again will keep the run from returning.
+    pub fn run_threaded(&mut self, monitor: &mut impl Monitor) -> Result<Outcome, NodeError> {
+        let n = self.len();
+        let empty_input = self.empty_input;
+        let mut nodes = std::mem::take(&mut self.nodes);
+        let (report, reports) = channel();
+        let mut remotes = Vec::new();
+        let mut stopped = self.halted.clone();
+
+        let result = thread::scope(|scope| {
+            let mut handles = Vec::new();
+            for (i, computer) in nodes.iter_mut().enumerate() {
+                let (input, receiver) = channel();
+                let mut remote = Remote {
+                    input,
+                    delivered: 0,
+                    waiting: false,
+                };
+                remote.deliver(self.inboxes[i].drain(..).collect());
+                remotes.push(remote);
+                if stopped[i] {
+                    handles.push(None);
+                    continue;
+                }
+                let report = report.clone();
+                handles.push(Some(scope.spawn(move || {
+                    let mut read = 0;
+                    let mut reported = false;
+                    let mut next_input = || {
+                        match receiver.try_recv() {
+                            Ok(value) => {
+                                read += 1;
+                                reported = false;
+                                return Some(value);
+                            }
+                            Err(TryRecvError::Disconnected) => return None,
+                            Err(TryRecvError::Empty) => {}
+                        }
+                        if !reported {
+                            let _ = report.send(Report::Waiting(i, read));
+                            reported = true;
+                        }
+                        if let Some(value) = empty_input {
+                            thread::yield_now();
+                            return Some(value);
+                        }
+                        let value = receiver.recv().ok()?;
+                        read += 1;
+                        reported = false;
+                        Some(value)
+                    };
+                    let mut output = |value| {
+                        let _ = report.send(Report::Output(i, value));
+                    };
+                    let state = computer.run_io(&mut next_input, &mut output);
+                    let _ = report.send(Report::Stopped(i, state));
+                    receiver
+                })));
+            }
+            drop(report);
+
+            let result = loop {
+                match reports.recv() {
+                    Ok(Report::Output(i, value)) => {
+                        if let Some((to, values)) = self.collect(i, value) {
+                            match self.dispatch(i, to, values, monitor) {
+                                Some(deliveries) => {
+                                    for (node, values) in deliveries {
+                                        remotes[node].deliver(values);
+                                    }
+                                }
+                                None => break Ok(Outcome::Stopped),
+                            }
+                        }
+                    }
+                    Ok(Report::Waiting(i, read)) => {
+                        remotes[i].waiting = read == remotes[i].delivered
+                    }
+                    Ok(Report::Stopped(i, Ok(state))) => {
+                        stopped[i] = true;
+                        self.halted[i] = matches!(state, IntCodeState::Halted(_));
+                    }
+                    Ok(Report::Stopped(i, Err(error))) => break Err(NodeError { node: i, error }),
+                    Err(_) => break Ok(Outcome::Halted),
+                }
+                if stopped.iter().all(|&stopped| stopped) {
+                    break Ok(Outcome::Halted);
+                }
+                let idle = (0..n).all(|i| stopped[i] || remotes[i].waiting);
+                if idle {
+                    match monitor.event(Event::Idle) {
+                        Action::Send(node, values) if node < n => remotes[node].deliver(values),
+                        Action::Send(..) | Action::Continue if empty_input.is_some() => {
+                            // Don't report the same idle period again.
+                            remotes.iter_mut().for_each(|remote| remote.waiting = false)
+                        }
+                        Action::Send(..) | Action::Continue => break Ok(Outcome::Idle),
+                        Action::Stop => break Ok(Outcome::Stopped),
+                    }
+                }
+            };
+
+            // Closing the inputs stops the nodes that are still running.
+            remotes.clear();
+            for (i, handle) in handles.into_iter().enumerate() {
+                if let Some(handle) = handle {
+                    let receiver = handle.join().expect("IntCode node panicked");
+                    self.inboxes[i].extend(receiver.try_iter());
+                }
+            }
+            result
+        });
+        self.nodes = nodes;
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::asm::assemble;
+
+    fn amplifiers(settings: &[isize]) -> Network {
+        // The day 7 feedback loop example.
+        let program = vec![
+            3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1,
+            28, 1005, 28, 6, 99, 0, 0, 5,
+        ];
+        let nodes = settings
+            .iter()
+            .map(|_| IntCode::new(program.clone(), vec![]))
+            .collect();
+        let n = settings.len();
+        let mut network = Network::new(nodes, Links::Direct((1..=n).map(|i| i % n).collect()));
+        for (i, &setting) in settings.iter().enumerate() {
+            network.send(i, &[setting]);
+        }
+        network.send(0, &[0]);
+        network
+    }
+
+    /// Each node reads its address, then for every value it is sent passes `value + 1` on to
+    /// the next address as a 2 word packet. It polls rather than blocks.
+    fn relays(n: usize) -> Network {
+        let program = assemble(
+            "       IN [addr]
+                    ADD [addr], #1, [next]
+             loop:  IN [x]
+                    EQ [x], #-1, [t]
+                    JT [t], #loop
+                    ADD [x], #1, [x]
+                    OUT [next]
+                    OUT [x]
+                    JT #1, #loop
+             addr:  DATA 0
+             next:  DATA 0
+             x:     DATA 0
+             t:     DATA 0",
+        )
+        .unwrap();
+        let nodes = (0..n)
+            .map(|_| IntCode::new(program.clone(), vec![]))
+            .collect();
+        let mut network = Network::new(nodes, Links::Packets { len: 2 }).with_empty_input(-1);
+        for i in 0..n {
+            network.send(i, &[i as isize]);
+        }
+        network
+    }
+
+    #[test]
+    fn test_ring() {
+        let mut network = amplifiers(&[9, 8, 7, 6, 5]);
+        assert_eq!(network.run(&mut ()), Ok(Outcome::Halted));
+        assert_eq!(network.outputs(4).last(), Some(&139629729));
+        assert!((0..5).all(|i| network.is_halted(i)));
+
+        let mut network = amplifiers(&[9, 8, 7, 6, 5]);
+        assert_eq!(network.run_threaded(&mut ()), Ok(Outcome::Halted));
+        assert_eq!(network.outputs(4).last(), Some(&139629729));
+    }
+
+    fn relay_monitor(log: &mut Vec<(usize, isize, Vec<isize>)>) -> impl Monitor + '_ {
+        move |event: Event<'_>| match event {
+            Event::Message { from, to, values } => {
+                log.push((from, to, values.to_vec()));
+                Action::Continue
+            }
+            Event::Idle if log.len() < 3 => Action::Send(0, vec![10]),
+            Event::Idle => Action::Stop,
+        }
+    }
+
+    #[test]
+    fn test_packets() {
+        let mut log = Vec::new();
+        let mut network = relays(3);
+        assert_eq!(
+            network.run(&mut relay_monitor(&mut log)),
+            Ok(Outcome::Stopped)
+        );
+        assert_eq!(
+            log,
+            vec![(0, 1, vec![11]), (1, 2, vec![12]), (2, 3, vec![13])]
+        );
+        assert_eq!(network.outputs(2), &[3, 13]);
+
+        let mut log = Vec::new();
+        let mut network = relays(3);
+        assert_eq!(
+            network.run_threaded(&mut relay_monitor(&mut log)),
+            Ok(Outcome::Stopped)
+        );
+        assert_eq!(
+            log,
+            vec![(0, 1, vec![11]), (1, 2, vec![12]), (2, 3, vec![13])]
+        );
+    }
+
+    #[test]
+    fn test_idle() {
+        let mut network = relays(2);
+        network.empty_input = None;
+        assert_eq!(network.run(&mut ()), Ok(Outcome::Idle));
+
+        let mut network = relays(2);
+        network.empty_input = None;
+        assert_eq!(network.run_threaded(&mut ()), Ok(Outcome::Idle));
+        network.send(0, &[1]);
+        let mut to = None;
+        let outcome = network.run(&mut |event: Event<'_>| match event {
+            Event::Message { to: 2, values, .. } => {
+                to = Some(values[0]);
+                Action::Stop
+            }
+            _ => Action::Continue,
+        });
+        assert_eq!(outcome, Ok(Outcome::Stopped));
+        assert_eq!(to, Some(3));
+    }
+
+    #[test]
+    fn test_send_to_missing_node() {
+        let mut network = relays(2);
+        assert!(!network.send(2, &[1]));
+        network.empty_input = None;
+        let mut monitor = |event: Event<'_>| match event {
+            Event::Message { .. } => Action::Send(7, vec![1]),
+            Event::Idle => Action::Send(2, vec![1]),
+        };
+        assert_eq!(network.run(&mut monitor), Ok(Outcome::Idle));
+
+        let mut network = relays(2);
+        network.empty_input = None;
+        network.send(0, &[1]);
+        assert_eq!(network.run_threaded(&mut monitor), Ok(Outcome::Idle));
+        assert_eq!(network.outputs(0), &[1, 2]);
+    }
+
+    #[test]
+    fn test_error() {
+        let mut network = Network::new(
+            vec![
+                IntCode::new(vec![99], vec![]),
+                IntCode::new(vec![42], vec![]),
+            ],
+            Links::Direct(vec![1, 0]),
+        );
+        let error = network.run(&mut ()).unwrap_err();
+        assert_eq!(error.node, 1);
+        assert_eq!(error.to_string(), "node 1: [0] 42: unknown opcode 42");
+    }
+}
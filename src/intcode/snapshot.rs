@@ -0,0 +1,207 @@
+//! Snapshots of the complete VM state, for backtracking searches and for saving to disk.
+//!
+//! Cloning an [`IntCode`] is copy-on-write (see [`memory`](super::memory)), so
+//! [`IntCode::fork`] and [`IntCode::snapshot`] are cheap enough to take at every branch point.
+
+use std::io::{self, BufRead, Write};
+
+use super::{memory::Memory, IntCode};
+
+const HEADER: &str = "intcode-snapshot 1";
+
+/// The full state of a VM: memory, instruction pointer, relative base and I/O buffers.
+#[derive(Debug, Clone)]
+pub struct Snapshot(IntCode);
+
+impl IntCode {
+    /// An independent copy of this VM that shares memory until either side writes to it.
+    pub fn fork(&self) -> IntCode {
+        self.clone()
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot(self.clone())
+    }
+
+    /// Returns the VM to the state captured in `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        *self = snapshot.0.clone();
+    }
+}
+
+impl From<Snapshot> for IntCode {
+    fn from(snapshot: Snapshot) -> IntCode {
+        snapshot.0
+    }
+}
+
+fn join(values: impl IntoIterator<Item = isize>) -> String {
+    values
+        .into_iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn invalid(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn parse<T: std::str::FromStr>(value: &str) -> io::Result<T> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("bad value {:?}", value)))
+}
+
+fn parse_list(values: &str) -> io::Result<Vec<isize>> {
+    if values.is_empty() {
+        return Ok(Vec::new());
+    }
+    values.split(',').map(parse).collect()
+}
+
+impl Snapshot {
+    /// Writes the snapshot in a line-based text format.
+    pub fn save(&self, mut writer: impl Write) -> io::Result<()> {
+        let computer = &self.0;
+        writeln!(writer, "{}", HEADER)?;
+        writeln!(writer, "ip {}", computer.index)?;
+        writeln!(writer, "relative_base {}", computer.relative_base)?;
+        writeln!(writer, "strict {}", computer.strict)?;
+        writeln!(writer, "checked {}", computer.checked)?;
+        match computer.program.limit() {
+            Some(limit) => writeln!(writer, "limit {}", limit)?,
+            None => writeln!(writer, "limit none")?,
+        }
+        writeln!(writer, "input {}", join(computer.input.iter().copied()))?;
+        writeln!(writer, "output {}", join(computer.output.iter().copied()))?;
+        writeln!(
+            writer,
+            "memory {}",
+            join(computer.program.dense().iter().copied())
+        )?;
+        for (start, words) in computer.program.pages() {
+            writeln!(writer, "page {} {}", start, join(words.iter().copied()))?;
+        }
+        Ok(())
+    }
+
+    /// Reads a snapshot written by [`save`](Snapshot::save).
+    pub fn load(reader: impl BufRead) -> io::Result<Snapshot> {
+        let mut lines = reader.lines();
+        if lines.next().transpose()?.as_deref() != Some(HEADER) {
+            return Err(invalid("not an IntCode snapshot"));
+        }
+        let mut index = 0;
+        let mut relative_base = 0;
+        let mut strict = false;
+        let mut checked = false;
+        let mut limit = None;
+        let mut input = Vec::new();
+        let mut output = Vec::new();
+        let mut dense = Vec::new();
+        let mut pages = Vec::new();
+        for line in lines {
+            let line = line?;
+            let (key, value) = line.split_once(' ').unwrap_or((&line, ""));
+            match key {
+                "ip" => index = parse(value)?,
+                "relative_base" => relative_base = parse(value)?,
+                "strict" => strict = parse(value)?,
+                "checked" => checked = parse(value)?,
+                "limit" if value == "none" => limit = None,
+                "limit" => limit = Some(parse(value)?),
+                "input" => input = parse_list(value)?,
+                "output" => output = parse_list(value)?,
+                "memory" => dense = parse_list(value)?,
+                "page" => {
+                    let (start, words) = value.split_once(' ').unwrap_or((value, ""));
+                    pages.push((parse(start)?, parse_list(words)?));
+                }
+                _ => return Err(invalid(format!("unexpected line {:?}", line))),
+            }
+        }
+        let program = Memory::from_parts(dense, pages, limit).ok_or_else(|| invalid("bad page"))?;
+        Ok(Snapshot(IntCode {
+            program,
+            input: input.into(),
+            index,
+            output,
+            relative_base,
+            strict,
+            checked,
+            cache: Default::default(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{asm::assemble, IntCodeState};
+
+    fn counter() -> IntCode {
+        // Adds each input to a running total stored far away, outputting the total.
+        let program = assemble(
+            "       ARB #1048576
+             start: IN [x]
+                    ADD rb+0, [x], rb+0
+                    OUT rb+0
+                    JT #1, #start
+             x:     DATA 0",
+        )
+        .unwrap();
+        IntCode::builder(program).memory_limit(1 << 16).build()
+    }
+
+    #[test]
+    fn test_fork_and_restore() {
+        let mut computer = counter();
+        computer.set_input(vec![5]);
+        assert_eq!(computer.run(1), Ok(IntCodeState::Output(vec![5])));
+        let snapshot = computer.snapshot();
+
+        let mut fork = computer.fork();
+        fork.set_input(vec![10]);
+        fork.run(2).unwrap();
+        assert_eq!(fork.output(), &[5, 15]);
+
+        computer.set_input(vec![1]);
+        computer.run(2).unwrap();
+        assert_eq!(computer.output(), &[5, 6]);
+
+        computer.restore(&snapshot);
+        computer.set_input(vec![2]);
+        computer.run(2).unwrap();
+        assert_eq!(computer.output(), &[5, 7]);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let mut computer = counter();
+        computer.set_input(vec![5, 6]);
+        computer.run(1).unwrap();
+
+        let mut saved = Vec::new();
+        computer.snapshot().save(&mut saved).unwrap();
+        let mut loaded = IntCode::from(Snapshot::load(&saved[..]).unwrap());
+        assert_eq!(loaded.input(), &[6]);
+        assert_eq!(loaded.output(), &[5]);
+        assert_eq!(loaded.memory(), computer.memory());
+        assert_eq!(loaded.read(1048576), 5);
+
+        let mut resaved = Vec::new();
+        loaded.snapshot().save(&mut resaved).unwrap();
+        assert_eq!(saved, resaved);
+
+        loaded.run(2).unwrap();
+        assert_eq!(loaded.output(), &[5, 11]);
+    }
+
+    #[test]
+    fn test_load_errors() {
+        assert!(Snapshot::load(&b"hello"[..]).is_err());
+        assert!(Snapshot::load(&b"intcode-snapshot 1\nip x\n"[..]).is_err());
+        assert!(Snapshot::load(&b"intcode-snapshot 1\npage 3 1,2\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,426 @@
+//! Declarative descriptions of IntCode networks, built in code or read from a small config file.
+//!
+//! A [`Topology`] says how many copies of a program to run, how they are wired together, what
+//! each is sent before it starts and how the run ends. [`Topology::run`] builds the
+//! [`Network`] and returns a [`Report`] of everything each node output.
+//!
+//! The config format has one directive per line, with `#` starting a comment. The first
+//! directive gives the wiring and the rest are options:
+//!
+//! ```text
+//! # Day 7, part two
+//! ring 5
+//! phases 9,8,7,6,5
+//! input 0 0
+//! ```
+//!
+//! | Directive             | Meaning                                                         |
+//! |-----------------------|-----------------------------------------------------------------|
+//! | `chain N`             | node `i` feeds node `i + 1`, and the last feeds the outside     |
+//! | `ring N`              | a chain where the last node feeds the first                     |
+//! | `bus N LEN`           | nodes send `LEN` word packets, led by the destination address   |
+//! | `links T0,T1,...`     | node `i` feeds node `Ti`                                        |
+//! | `phases A,B,...`      | sends `A` to node 0, `B` to node 1 and so on                    |
+//! | `addresses`           | sends every node its own index                                  |
+//! | `input NODE A,B,...`  | sends values to one node                                        |
+//! | `empty-input V`       | nodes read `V` instead of waiting when they have no input       |
+//! | `nat ADDR`            | a day 23 NAT listens on `ADDR`                                  |
+//! | `stop-at ADDR`        | stops at the first message to `ADDR`                            |
+//! | `threaded`            | runs each node on its own thread                                |
+
+use std::{error::Error, fmt::Display, str::FromStr};
+
+use super::{
+    network::{Action, Event, Links, Network, NodeError, Outcome},
+    IntCode,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Wiring {
+    Chain,
+    Ring,
+    Bus {
+        packet_len: usize,
+    },
+    /// Node `i` feeds node `targets[i]`. Targets with no node behind them feed the outside.
+    Custom(Vec<usize>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Topology {
+    nodes: usize,
+    wiring: Wiring,
+    inputs: Vec<Vec<isize>>,
+    empty_input: Option<isize>,
+    nat: Option<isize>,
+    stop_at: Option<isize>,
+    threaded: bool,
+}
+
+/// What a run of a [`Topology`] produced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub outcome: Outcome,
+    /// Everything each node output, including packet addresses.
+    pub outputs: Vec<Vec<isize>>,
+    /// Messages to addresses with no node behind them as `(from, to, values)`, in the order
+    /// they were sent.
+    pub external: Vec<(usize, isize, Vec<isize>)>,
+    /// Every packet the NAT sent to node 0.
+    pub nat: Vec<Vec<isize>>,
+}
+
+impl Report {
+    pub fn last_output(&self, node: usize) -> Option<isize> {
+        self.outputs[node].last().copied()
+    }
+}
+
+fn join(values: &[isize]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "outcome: {:?}", self.outcome)?;
+        for (node, outputs) in self.outputs.iter().enumerate() {
+            writeln!(f, "node {}: {}", node, join(outputs))?;
+        }
+        for (from, to, values) in &self.external {
+            writeln!(f, "{} -> {}: {}", from, to, join(values))?;
+        }
+        for packet in &self.nat {
+            writeln!(f, "nat -> 0: {}", join(packet))?;
+        }
+        Ok(())
+    }
+}
+
+impl Topology {
+    fn new(nodes: usize, wiring: Wiring) -> Topology {
+        Topology {
+            nodes,
+            wiring,
+            inputs: vec![Vec::new(); nodes],
+            empty_input: None,
+            nat: None,
+            stop_at: None,
+            threaded: false,
+        }
+    }
+
+    pub fn chain(nodes: usize) -> Topology {
+        Topology::new(nodes, Wiring::Chain)
+    }
+
+    pub fn ring(nodes: usize) -> Topology {
+        Topology::new(nodes, Wiring::Ring)
+    }
+
+    pub fn bus(nodes: usize, packet_len: usize) -> Topology {
+        Topology::new(nodes, Wiring::Bus { packet_len })
+    }
+
+    pub fn custom(targets: Vec<usize>) -> Topology {
+        Topology::new(targets.len(), Wiring::Custom(targets))
+    }
+
+    /// Sends one setting to each node, in node order.
+    pub fn phases(mut self, phases: &[isize]) -> Topology {
+        for (inputs, &phase) in self.inputs.iter_mut().zip(phases) {
+            inputs.push(phase);
+        }
+        self
+    }
+
+    /// Sends every node its own index.
+    pub fn addresses(mut self) -> Topology {
+        for (node, inputs) in self.inputs.iter_mut().enumerate() {
+            inputs.push(node as isize);
+        }
+        self
+    }
+
+    pub fn input(mut self, node: usize, values: &[isize]) -> Topology {
+        self.inputs[node].extend(values);
+        self
+    }
+
+    /// See [`Network::with_empty_input`].
+    pub fn empty_input(mut self, value: isize) -> Topology {
+        self.empty_input = Some(value);
+        self
+    }
+
+    /// Holds the latest packet sent to `address` and sends it to node 0 whenever the network
+    /// goes idle. The run stops when the NAT would send the same Y value twice in a row.
+    pub fn nat(mut self, address: isize) -> Topology {
+        self.nat = Some(address);
+        self
+    }
+
+    /// Stops the run at the first message to `address`.
+    pub fn stop_at(mut self, address: isize) -> Topology {
+        self.stop_at = Some(address);
+        self
+    }
+
+    pub fn threaded(mut self) -> Topology {
+        self.threaded = true;
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes == 0
+    }
+
+    /// Builds the network, with every node running its own copy of `program`.
+    pub fn network(&self, program: &[isize]) -> Network {
+        let n = self.nodes;
+        let links = match &self.wiring {
+            Wiring::Chain => Links::Direct((1..=n).collect()),
+            Wiring::Ring => Links::Direct((1..=n).map(|i| i % n).collect()),
+            Wiring::Bus { packet_len } => Links::Packets { len: *packet_len },
+            Wiring::Custom(targets) => Links::Direct(targets.clone()),
+        };
+        let nodes = (0..n)
+            .map(|_| IntCode::new(program.to_vec(), vec![]))
+            .collect();
+        let mut network = Network::new(nodes, links);
+        if let Some(value) = self.empty_input {
+            network = network.with_empty_input(value);
+        }
+        for (node, inputs) in self.inputs.iter().enumerate() {
+            network.send(node, inputs);
+        }
+        network
+    }
+
+    /// Runs the network until it halts, goes idle or meets a stop condition. Without a NAT the
+    /// run ends the first time the network goes idle.
+    pub fn run(&self, program: &[isize]) -> Result<Report, NodeError> {
+        let mut network = self.network(program);
+        let mut external = Vec::new();
+        let mut nat = Vec::new();
+        let mut held: Option<Vec<isize>> = None;
+        let mut idle = false;
+        let mut monitor = |event: Event<'_>| match event {
+            Event::Message { from, to, values } => {
+                if usize::try_from(to).map_or(true, |to| to >= self.nodes) {
+                    external.push((from, to, values.to_vec()));
+                }
+                if Some(to) == self.nat {
+                    held = Some(values.to_vec());
+                }
+                if Some(to) == self.stop_at {
+                    Action::Stop
+                } else {
+                    Action::Continue
+                }
+            }
+            Event::Idle => match &held {
+                // Only send while the Y value keeps changing.
+                Some(packet)
+                    if nat
+                        .last()
+                        .is_none_or(|last: &Vec<_>| last.last() != packet.last()) =>
+                {
+                    nat.push(packet.clone());
+                    Action::Send(0, packet.clone())
+                }
+                _ => {
+                    idle = held.is_none();
+                    Action::Stop
+                }
+            },
+        };
+        let mut outcome = if self.threaded {
+            network.run_threaded(&mut monitor)?
+        } else {
+            network.run(&mut monitor)?
+        };
+        if idle {
+            outcome = Outcome::Idle;
+        }
+        Ok(Report {
+            outcome,
+            outputs: (0..self.nodes)
+                .map(|node| network.outputs(node).to_vec())
+                .collect(),
+            external,
+            nat,
+        })
+    }
+}
+
+/// A problem with a topology config, with the (1-based) line it was found on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopologyError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for TopologyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for TopologyError {}
+
+fn parse_value<T: FromStr>(value: Option<&str>) -> Result<T, String> {
+    let value = value.ok_or("missing value")?;
+    value.parse().map_err(|_| format!("bad value {:?}", value))
+}
+
+fn parse_list<T: FromStr>(values: Option<&str>) -> Result<Vec<T>, String> {
+    let values = values.ok_or("missing values")?;
+    values.split(',').map(|v| parse_value(Some(v))).collect()
+}
+
+fn parse_line(topology: Option<Topology>, line: &str) -> Result<Topology, String> {
+    let mut words = line.split_whitespace();
+    let directive = words.next().unwrap();
+    let topology = match (topology, directive) {
+        (None, "chain") => Topology::chain(parse_value(words.next())?),
+        (None, "ring") => Topology::ring(parse_value(words.next())?),
+        (None, "bus") => {
+            let nodes = parse_value(words.next())?;
+            Topology::bus(nodes, parse_value(words.next())?)
+        }
+        (None, "links") => Topology::custom(parse_list(words.next())?),
+        (None, _) => return Err(format!("expected the wiring, found {:?}", directive)),
+        (Some(_), "chain" | "ring" | "bus" | "links") => {
+            return Err("the wiring is already set".to_string())
+        }
+        (Some(topology), "phases") => topology.phases(&parse_list(words.next())?),
+        (Some(topology), "addresses") => topology.addresses(),
+        (Some(topology), "input") => {
+            let node: usize = parse_value(words.next())?;
+            if node >= topology.len() {
+                return Err(format!("no node {}", node));
+            }
+            topology.input(node, &parse_list(words.next())?)
+        }
+        (Some(topology), "empty-input") => topology.empty_input(parse_value(words.next())?),
+        (Some(topology), "nat") => topology.nat(parse_value(words.next())?),
+        (Some(topology), "stop-at") => topology.stop_at(parse_value(words.next())?),
+        (Some(topology), "threaded") => topology.threaded(),
+        (Some(_), _) => return Err(format!("unknown directive {:?}", directive)),
+    };
+    match words.next() {
+        Some(word) => Err(format!("unexpected {:?}", word)),
+        None => Ok(topology),
+    }
+}
+
+impl FromStr for Topology {
+    type Err = TopologyError;
+
+    fn from_str(config: &str) -> Result<Topology, TopologyError> {
+        let mut topology = None;
+        for (i, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            topology = Some(parse_line(topology, line).map_err(|message| TopologyError {
+                line: i + 1,
+                message,
+            })?);
+        }
+        topology.ok_or(TopologyError {
+            line: config.lines().count(),
+            message: "no wiring given".to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMPLIFIER: [isize; 29] = [
+        3, 26, 1001, 26, -4, 26, 3, 27, 1002, 27, 2, 27, 1, 27, 26, 27, 4, 27, 1001, 28, -1, 28,
+        1005, 28, 6, 99, 0, 0, 5,
+    ];
+
+    #[test]
+    fn test_chain() {
+        // The first day 7 example.
+        let program = [
+            3, 15, 3, 16, 1002, 16, 10, 16, 1, 16, 15, 15, 4, 15, 99, 0, 0,
+        ];
+        let report = Topology::chain(5)
+            .phases(&[4, 3, 2, 1, 0])
+            .input(0, &[0])
+            .run(&program)
+            .unwrap();
+        assert_eq!(report.outcome, Outcome::Halted);
+        assert_eq!(
+            report.outputs,
+            vec![vec![4], vec![43], vec![432], vec![4321], vec![43210]]
+        );
+        assert_eq!(report.external, vec![(4, 5, vec![43210])]);
+    }
+
+    #[test]
+    fn test_config() {
+        let config = "# Day 7, part two
+                      ring 5
+                      phases 9,8,7,6,5
+                      input 0 0  # start the loop";
+        let topology: Topology = config.parse().unwrap();
+        assert_eq!(
+            topology,
+            Topology::ring(5).phases(&[9, 8, 7, 6, 5]).input(0, &[0])
+        );
+        let report = topology.run(&AMPLIFIER).unwrap();
+        assert_eq!(report.last_output(4), Some(139629729));
+        assert_eq!(topology.threaded().run(&AMPLIFIER), Ok(report.clone()));
+        let text = report.to_string();
+        assert_eq!(
+            text.lines().take(2).collect::<Vec<_>>(),
+            vec!["outcome: Halted", "node 0: 5,263,8519,272711,8726855"]
+        );
+
+        let topology: Topology = "links 1,2,0\naddresses\nempty-input -1\nnat 255\nstop-at 3"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            topology,
+            Topology::custom(vec![1, 2, 0])
+                .addresses()
+                .empty_input(-1)
+                .nat(255)
+                .stop_at(3)
+        );
+    }
+
+    #[test]
+    fn test_config_errors() {
+        let error = |config: &str| config.parse::<Topology>().unwrap_err().to_string();
+        assert_eq!(error(""), "line 0: no wiring given");
+        assert_eq!(
+            error("phases 1,2"),
+            r#"line 1: expected the wiring, found "phases""#
+        );
+        assert_eq!(error("ring 2\nring 3"), "line 2: the wiring is already set");
+        assert_eq!(error("ring 2\ninput 2 1"), "line 2: no node 2");
+        assert_eq!(error("bus 2"), "line 1: missing value");
+        assert_eq!(error("chain x"), r#"line 1: bad value "x""#);
+        assert_eq!(error("chain 2 3"), r#"line 1: unexpected "3""#);
+        assert_eq!(
+            error("chain 2\nnats 3"),
+            r#"line 2: unknown directive "nats""#
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Execution tracing and profiling hooks for [`IntCode::run_traced`](super::IntCode::run_traced).
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, Write},
+};
+
+use super::{Executed, Opcode};
+
+/// Receives every instruction executed by the VM.
+pub trait Tracer<W = isize> {
+    fn trace(&mut self, executed: &Executed<W>);
+}
+
+/// The no-op tracer used by [`IntCode::run`](super::IntCode::run).
+impl<W> Tracer<W> for () {
+    fn trace(&mut self, _executed: &Executed<W>) {}
+}
+
+impl<W, F: FnMut(&Executed<W>)> Tracer<W> for F {
+    fn trace(&mut self, executed: &Executed<W>) {
+        self(executed)
+    }
+}
+
+/// Formats an executed instruction as a single line of JSON.
+pub fn to_json<W: Display>(executed: &Executed<W>) -> String {
+    let operands = executed.operands[..executed.opcode.parameter_count()]
+        .iter()
+        .map(|operand| operand.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let write = match &executed.write {
+        Some((address, value)) => format!("[{},{}]", address, value),
+        None => "null".to_string(),
+    };
+    let output = match &executed.output {
+        Some(value) => value.to_string(),
+        None => "null".to_string(),
+    };
+    format!(
+        r#"{{"ip":{},"opcode":"{}","operands":[{}],"write":{},"output":{}}}"#,
+        executed.ip,
+        executed.opcode.mnemonic(),
+        operands,
+        write,
+        output
+    )
+}
+
+/// Writes the trace as JSON lines. The first I/O error stops the trace and is returned by
+/// [`finish`](JsonLines::finish).
+#[derive(Debug)]
+pub struct JsonLines<W: Write> {
+    writer: W,
+    error: Option<io::Error>,
+}
+
+impl<W: Write> JsonLines<W> {
+    pub fn new(writer: W) -> JsonLines<W> {
+        JsonLines {
+            writer,
+            error: None,
+        }
+    }
+
+    pub fn finish(mut self) -> io::Result<W> {
+        match self.error {
+            Some(e) => Err(e),
+            None => {
+                self.writer.flush()?;
+                Ok(self.writer)
+            }
+        }
+    }
+}
+
+impl<W: Write, V: Display> Tracer<V> for JsonLines<W> {
+    fn trace(&mut self, executed: &Executed<V>) {
+        if self.error.is_none() {
+            if let Err(e) = writeln!(self.writer, "{}", to_json(executed)) {
+                self.error = Some(e);
+            }
+        }
+    }
+}
+
+/// Collects a summary profile: instruction counts per opcode, execution counts per address,
+/// the highest address executed, read or written and the total number of cycles.
+#[derive(Debug, Clone, Default)]
+pub struct Profiler {
+    opcodes: HashMap<Opcode, usize>,
+    addresses: HashMap<usize, usize>,
+    max_address: usize,
+    cycles: usize,
+}
+
+impl Profiler {
+    pub fn new() -> Profiler {
+        Profiler::default()
+    }
+
+    pub fn cycles(&self) -> usize {
+        self.cycles
+    }
+
+    pub fn max_address(&self) -> usize {
+        self.max_address
+    }
+
+    /// Instruction counts per opcode, most frequent first.
+    pub fn opcode_counts(&self) -> Vec<(Opcode, usize)> {
+        let mut counts: Vec<_> = self.opcodes.iter().map(|(&op, &n)| (op, n)).collect();
+        counts.sort_by_key(|&(op, n)| (std::cmp::Reverse(n), op.code()));
+        counts
+    }
+
+    /// The `n` most executed instruction addresses with their counts.
+    pub fn hottest(&self, n: usize) -> Vec<(usize, usize)> {
+        let mut counts: Vec<_> = self.addresses.iter().map(|(&a, &n)| (a, n)).collect();
+        counts.sort_by_key(|&(address, n)| (std::cmp::Reverse(n), address));
+        counts.truncate(n);
+        counts
+    }
+}
+
+impl<W> Tracer<W> for Profiler {
+    fn trace(&mut self, executed: &Executed<W>) {
+        self.cycles += 1;
+        *self.opcodes.entry(executed.opcode).or_insert(0) += 1;
+        *self.addresses.entry(executed.ip).or_insert(0) += 1;
+        let end = executed.ip + executed.opcode.parameter_count();
+        let written = executed.write.as_ref().map_or(0, |&(address, _)| address);
+        let read = executed.reads.iter().flatten().copied().max().unwrap_or(0);
+        self.max_address = self.max_address.max(end).max(written).max(read);
+    }
+}
+
+impl Display for Profiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "cycles: {}", self.cycles)?;
+        writeln!(f, "max address: {}", self.max_address)?;
+        writeln!(f, "opcodes:")?;
+        for (opcode, count) in self.opcode_counts() {
+            writeln!(f, "  {:<4}{}", opcode.mnemonic(), count)?;
+        }
+        writeln!(f, "hottest addresses:")?;
+        for (address, count) in self.hottest(10) {
+            writeln!(f, "  {:04}: {}", address, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::intcode::{asm::assemble, fixtures::countdown, IntCode};
+
+    #[test]
+    fn test_profiler() {
+        let mut profiler = Profiler::new();
+        countdown().run_traced(0, &mut profiler).unwrap();
+        assert_eq!(profiler.cycles(), 9);
+        assert_eq!(profiler.max_address(), 12);
+        assert_eq!(
+            profiler.opcode_counts(),
+            vec![
+                (Opcode::Add, 3),
+                (Opcode::Output, 3),
+                (Opcode::JumpIfTrue, 3)
+            ]
+        );
+        assert_eq!(profiler.hottest(2), vec![(0, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn test_profiler_reads() {
+        // The data is only ever read, by position and then relative to the base.
+        let program = assemble("OUT [data]\nARB #3\nOUT rb+5\nHLT\ndata: DATA 7\nDATA 8").unwrap();
+        let mut profiler = Profiler::new();
+        IntCode::new(program, vec![])
+            .run_traced(0, &mut profiler)
+            .unwrap();
+        assert_eq!(profiler.max_address(), 8);
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut tracer = JsonLines::new(Vec::new());
+        countdown().run_traced(0, &mut tracer).unwrap();
+        let trace = String::from_utf8(tracer.finish().unwrap()).unwrap();
+        let lines: Vec<&str> = trace.lines().collect();
+        assert_eq!(lines.len(), 9);
+        assert_eq!(
+            lines[0],
+            r#"{"ip":0,"opcode":"OUT","operands":[3],"write":null,"output":3}"#
+        );
+        assert_eq!(
+            lines[1],
+            r#"{"ip":2,"opcode":"ADD","operands":[3,-1,12],"write":[12,2],"output":null}"#
+        );
+        assert_eq!(
+            lines[2],
+            r#"{"ip":6,"opcode":"JT","operands":[2,0],"write":null,"output":null}"#
+        );
+    }
+
+    #[test]
+    fn test_closure() {
+        let mut ips = Vec::new();
+        countdown()
+            .run_traced(0, &mut |executed: &Executed| ips.push(executed.ip))
+            .unwrap();
+        assert_eq!(ips, vec![0, 2, 6, 0, 2, 6, 0, 2, 6]);
+    }
+}
@@ -0,0 +1,48 @@
+//! Word types for the VM.
+//!
+//! [`IntCode`](super::IntCode) uses `isize`, which is all the puzzles need. Programs that work
+//! with larger numbers can run on [`Vm<i64>`](super::Vm), `Vm<i128>` or `Vm<BigInt>` instead.
+
+use std::fmt::{Debug, Display};
+
+use num::{BigInt, CheckedAdd, CheckedMul, FromPrimitive, One, ToPrimitive, Zero};
+
+/// An integer type the VM can use for memory, input and output.
+pub trait Word:
+    Clone + Debug + Display + Ord + Zero + One + FromPrimitive + ToPrimitive + CheckedAdd + CheckedMul
+{
+    /// Addition that wraps around on overflow, for types that can overflow.
+    fn wrapping_add(&self, other: &Self) -> Self;
+
+    /// Multiplication that wraps around on overflow, for types that can overflow.
+    fn wrapping_mul(&self, other: &Self) -> Self;
+}
+
+macro_rules! fixed_width_word {
+    ($($t:ty),*) => {
+        $(
+            impl Word for $t {
+                fn wrapping_add(&self, other: &Self) -> Self {
+                    <$t>::wrapping_add(*self, *other)
+                }
+
+                fn wrapping_mul(&self, other: &Self) -> Self {
+                    <$t>::wrapping_mul(*self, *other)
+                }
+            }
+        )*
+    };
+}
+
+fixed_width_word!(isize, i64, i128);
+
+/// Never overflows, so wrapping and checked arithmetic give the same results.
+impl Word for BigInt {
+    fn wrapping_add(&self, other: &Self) -> Self {
+        self + other
+    }
+
+    fn wrapping_mul(&self, other: &Self) -> Self {
+        self * other
+    }
+}
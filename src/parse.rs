@@ -0,0 +1,338 @@
+//! Parsing for the puzzle inputs: IntCode programs, digit strings, character grids and
+//! line-based records. Every parser reports problems as a [`ParseError`] that points at the
+//! offending line and column, and tolerates the trailing newline at the end of the data files.
+//!
+//! ```
+//! use advent_of_code_2019::parse;
+//!
+//! assert_eq!(parse::intcode(&b"1,0,0,3,99\n"[..]), Ok(vec![1, 0, 0, 3, 99]));
+//! let error = parse::intcode(&b"1,0,x,3,99"[..]).unwrap_err();
+//! assert_eq!(error.to_string(), "line 1, column 5: bad number \"x\"");
+//! ```
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// A problem with the puzzle input. Lines and columns count from 1; columns count characters.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+    /// The read that failed, for input that couldn't be read at all. Shared so the error stays
+    /// cloneable.
+    io: Option<Arc<io::Error>>,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> ParseError {
+        ParseError {
+            line,
+            column,
+            message: message.into(),
+            io: None,
+        }
+    }
+
+    /// An error at byte `offset` of `text`.
+    pub fn at(text: &str, offset: usize, message: impl Into<String>) -> ParseError {
+        let before = &text[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        ParseError::new(
+            before.matches('\n').count() + 1,
+            before[line_start..].chars().count() + 1,
+            message,
+        )
+    }
+
+    /// Reading failed after `read`, the text that did arrive.
+    fn read_failed(read: &str, error: io::Error) -> ParseError {
+        let mut parse_error = ParseError::at(read, read.len(), error.to_string());
+        parse_error.io = Some(Arc::new(error));
+        parse_error
+    }
+
+    /// Takes out the read failure behind this error, if that is what it is.
+    pub fn into_io(self) -> Result<io::Error, ParseError> {
+        match self.io {
+            Some(error) => Ok(Arc::try_unwrap(error)
+                .unwrap_or_else(|error| io::Error::new(error.kind(), error.to_string()))),
+            None => Err(self),
+        }
+    }
+}
+
+impl PartialEq for ParseError {
+    fn eq(&self, other: &ParseError) -> bool {
+        let kind = |e: &ParseError| e.io.as_ref().map(|io| io.kind());
+        (self.line, self.column, &self.message, kind(self))
+            == (other.line, other.column, &other.message, kind(other))
+    }
+}
+
+impl Eq for ParseError {}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.io.as_deref().map(|e| e as &(dyn Error + 'static))
+    }
+}
+
+/// A problem within a single line, for the parsers passed to [`records`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LineError {
+    /// Byte offset from the start of the line.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl LineError {
+    /// An error at `part`, which must be a slice of `line`.
+    pub fn at(line: &str, part: &str, message: impl Into<String>) -> LineError {
+        LineError {
+            offset: (part.as_ptr() as usize).saturating_sub(line.as_ptr() as usize),
+            message: message.into(),
+        }
+    }
+
+    /// An error for a line that nom failed to parse, at the point where it gave up.
+    pub fn nom(line: &str, error: nom::Err<nom::error::Error<&str>>, expected: &str) -> LineError {
+        let message = format!("expected {}", expected);
+        match error {
+            nom::Err::Error(e) | nom::Err::Failure(e) => LineError::at(line, e.input, message),
+            nom::Err::Incomplete(_) => LineError::at(line, &line[line.len()..], message),
+        }
+    }
+}
+
+impl From<String> for LineError {
+    fn from(message: String) -> LineError {
+        LineError { offset: 0, message }
+    }
+}
+
+impl From<&str> for LineError {
+    fn from(message: &str) -> LineError {
+        LineError::from(message.to_string())
+    }
+}
+
+/// Parses `field`, a slice of `line`, as a number.
+pub fn number<T: FromStr>(line: &str, field: &str) -> Result<T, LineError> {
+    field
+        .trim()
+        .parse()
+        .map_err(|_| LineError::at(line, field, format!("bad number {:?}", field.trim())))
+}
+
+/// Reads the whole input as text. A failed read comes back as an error that
+/// [`into_io`](ParseError::into_io) turns back into the [`io::Error`].
+pub fn text(mut input: impl BufRead) -> Result<String, ParseError> {
+    let mut bytes = Vec::new();
+    if let Err(e) = input.read_to_end(&mut bytes) {
+        return Err(ParseError::read_failed(&String::from_utf8_lossy(&bytes), e));
+    }
+    String::from_utf8(bytes).map_err(|e| {
+        let valid = e.utf8_error().valid_up_to();
+        let text = String::from_utf8_lossy(&e.as_bytes()[..valid]);
+        ParseError::at(&text, valid, "invalid UTF-8")
+    })
+}
+
+/// Parses a comma-separated IntCode program.
+pub fn intcode(input: impl BufRead) -> Result<Vec<isize>, ParseError> {
+    let text = text(input)?;
+    let program = text.trim_end();
+    if program.is_empty() {
+        return Err(ParseError::at(&text, 0, "empty program"));
+    }
+    let mut offset = 0;
+    program
+        .split(',')
+        .map(|value| {
+            let start = offset + value.len() - value.trim_start().len();
+            offset += value.len() + 1;
+            match value.trim() {
+                "" => Err(ParseError::at(&text, start, "missing value")),
+                v => v
+                    .parse()
+                    .map_err(|_| ParseError::at(&text, start, format!("bad number {:?}", v))),
+            }
+        })
+        .collect()
+}
+
+/// Parses a single line of decimal digits.
+pub fn digits(input: impl BufRead) -> Result<Vec<u8>, ParseError> {
+    let text = text(input)?;
+    text.trim_end()
+        .char_indices()
+        .map(|(i, c)| match c.to_digit(10) {
+            Some(digit) => Ok(digit as u8),
+            None => Err(ParseError::at(
+                &text,
+                i,
+                format!("expected a digit, found {:?}", c),
+            )),
+        })
+        .collect()
+}
+
+/// Parses a grid of characters, one row per line, converting each character with `cell`.
+/// Rows keep their own lengths; blank lines at the end are dropped.
+pub fn grid<T>(
+    input: impl BufRead,
+    mut cell: impl FnMut(char) -> Option<T>,
+) -> Result<Vec<Vec<T>>, ParseError> {
+    let text = text(input)?;
+    let mut offset = 0;
+    let mut rows = text
+        .split('\n')
+        .map(|line| {
+            let start = offset;
+            offset += line.len() + 1;
+            line.trim_end_matches('\r')
+                .char_indices()
+                .map(|(i, c)| {
+                    cell(c).ok_or_else(|| {
+                        ParseError::at(&text, start + i, format!("unexpected {:?}", c))
+                    })
+                })
+                .collect::<Result<Vec<T>, ParseError>>()
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    while rows.last().is_some_and(Vec::is_empty) {
+        rows.pop();
+    }
+    Ok(rows)
+}
+
+/// Parses each non-blank line of `text` with `record`.
+pub fn records<'a, T>(
+    text: &'a str,
+    mut record: impl FnMut(&'a str) -> Result<T, LineError>,
+) -> Result<Vec<T>, ParseError> {
+    let mut offset = 0;
+    let mut records = Vec::new();
+    for line in text.split('\n') {
+        let start = offset;
+        offset += line.len() + 1;
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = record(line)
+            .map_err(|e| ParseError::at(text, start + e.offset.min(line.len()), e.message))?;
+        records.push(parsed);
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intcode() {
+        assert_eq!(intcode(&b"1, -2,3\n"[..]), Ok(vec![1, -2, 3]));
+        assert_eq!(
+            intcode(&b"1,,3"[..]).unwrap_err().to_string(),
+            "line 1, column 3: missing value"
+        );
+        assert_eq!(
+            intcode(&b"1,2,3,\n"[..]).unwrap_err().to_string(),
+            "line 1, column 7: missing value"
+        );
+        assert_eq!(
+            intcode(&b"\n"[..]).unwrap_err().to_string(),
+            "line 1, column 1: empty program"
+        );
+        assert_eq!(
+            intcode(&b"1,2\n,x"[..]).unwrap_err().to_string(),
+            "line 2, column 2: bad number \"x\""
+        );
+        assert_eq!(
+            intcode(&b"1,\xff"[..]).unwrap_err().to_string(),
+            "line 1, column 3: invalid UTF-8"
+        );
+    }
+
+    /// A reader that hands out some text and then fails.
+    struct Failing<'a>(&'a [u8]);
+
+    impl io::Read for Failing<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() {
+                return Err(io::Error::other("disk on fire"));
+            }
+            self.0.read(buf)
+        }
+    }
+
+    #[test]
+    fn test_read_failure() {
+        let error = intcode(io::BufReader::new(Failing(b"1,2\n3"))).unwrap_err();
+        assert_eq!(error.to_string(), "line 2, column 2: disk on fire");
+        assert!(error.source().is_some());
+        assert_eq!(error.into_io().unwrap().to_string(), "disk on fire");
+        assert!(intcode(&b"x"[..]).unwrap_err().into_io().is_err());
+    }
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits(&b"0123\n"[..]), Ok(vec![0, 1, 2, 3]));
+        assert_eq!(
+            digits(&b"01a3"[..]).unwrap_err().to_string(),
+            "line 1, column 3: expected a digit, found 'a'"
+        );
+    }
+
+    #[test]
+    fn test_grid() {
+        let cell = |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        };
+        assert_eq!(
+            grid(&b"#.\n.#\n\n"[..], cell),
+            Ok(vec![vec![true, false], vec![false, true]])
+        );
+        assert_eq!(
+            grid(&b"#.\n.?"[..], cell).unwrap_err().to_string(),
+            "line 2, column 2: unexpected '?'"
+        );
+    }
+
+    #[test]
+    fn test_records() {
+        fn orbit(line: &str) -> Result<(&str, usize), LineError> {
+            let (a, b) = line.split_once(')').ok_or("expected A)B")?;
+            Ok((a, number(line, b)?))
+        }
+        assert_eq!(records("A)1\n\nB)2\n", orbit), Ok(vec![("A", 1), ("B", 2)]));
+        assert_eq!(
+            records("A)1\nB)x", orbit).unwrap_err().to_string(),
+            "line 2, column 3: bad number \"x\""
+        );
+        assert_eq!(
+            records("A)1\nB", orbit).unwrap_err().to_string(),
+            "line 2, column 1: expected A)B"
+        );
+    }
+}
@@ -0,0 +1,410 @@
+//! Pictures of grids and game states: an [`Image`] can be printed to a terminal with ANSI
+//! colours or saved as a PPM or PNG file, and an [`Animation`] is a sequence of images that can
+//! be played in the terminal or saved frame by frame.
+//!
+//! ```
+//! use advent_of_code_2019::{
+//!     grid::Grid,
+//!     render::{Image, Rgb},
+//! };
+//!
+//! let grid = Grid::parse(&b"#.\n.#\n"[..], |c| Some(c == '#')).unwrap();
+//! let image = Image::from_grid(&grid, |&on| if on { Rgb::WHITE } else { Rgb::BLACK });
+//! assert_eq!(image.pixel(1, 1), Some(Rgb::WHITE));
+//!
+//! let mut png = Vec::new();
+//! image.write_png(&mut png).unwrap();
+//! assert!(png.starts_with(b"\x89PNG"));
+//! ```
+
+use std::{
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use crate::grid::{Grid, SparseGrid};
+
+/// A colour with 8 bits per channel.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+    pub const GREY: Rgb = Rgb(128, 128, 128);
+    pub const RED: Rgb = Rgb(220, 50, 47);
+    pub const GREEN: Rgb = Rgb(133, 153, 0);
+    pub const YELLOW: Rgb = Rgb(181, 137, 0);
+    pub const BLUE: Rgb = Rgb(38, 139, 210);
+}
+
+/// A rectangle of pixels stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Image {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Image {
+    /// A `width` by `height` image filled with `background`.
+    pub fn new(width: usize, height: usize, background: Rgb) -> Image {
+        Image {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    /// One pixel per cell, coloured by `colour`.
+    pub fn from_grid<T>(grid: &Grid<T>, mut colour: impl FnMut(&T) -> Rgb) -> Image {
+        Image {
+            width: grid.width(),
+            height: grid.height(),
+            pixels: grid.iter().map(|(_, cell)| colour(cell)).collect(),
+        }
+    }
+
+    /// One pixel per cell within the grid's bounds, with `background` where no cell is set.
+    pub fn from_sparse<T>(
+        grid: &SparseGrid<T>,
+        background: Rgb,
+        mut colour: impl FnMut(&T) -> Rgb,
+    ) -> Image {
+        let Some((min, max)) = grid.bounds() else {
+            return Image::new(0, 0, background);
+        };
+        let mut image = Image::new(
+            (max.x - min.x + 1) as usize,
+            (max.y - min.y + 1) as usize,
+            background,
+        );
+        for (point, cell) in grid.iter() {
+            let offset = (point.y - min.y) as usize * image.width + (point.x - min.x) as usize;
+            image.pixels[offset] = colour(cell);
+        }
+        image
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> Option<Rgb> {
+        (x < self.width && y < self.height).then(|| self.pixels[y * self.width + x])
+    }
+
+    /// The image with every pixel drawn as a `factor` by `factor` square.
+    pub fn scale(&self, factor: usize) -> Image {
+        let (width, height) = (self.width * factor, self.height * factor);
+        let pixels = (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x / factor, y / factor)))
+            .map(|(x, y)| self.pixels[y * self.width + x])
+            .collect();
+        Image {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// The image as 24-bit ANSI colour escapes, packing two rows into each line of text with
+    /// upper half blocks.
+    pub fn to_ansi(&self) -> String {
+        let mut text = String::new();
+        for y in (0..self.height).step_by(2) {
+            for x in 0..self.width {
+                let Rgb(r, g, b) = self.pixels[y * self.width + x];
+                let _ = write!(text, "\x1b[38;2;{};{};{}m", r, g, b);
+                match self.pixel(x, y + 1) {
+                    Some(Rgb(r, g, b)) => {
+                        let _ = write!(text, "\x1b[48;2;{};{};{}m", r, g, b);
+                    }
+                    None => text.push_str("\x1b[49m"),
+                }
+                text.push('▀');
+            }
+            text.push_str("\x1b[0m\n");
+        }
+        text
+    }
+
+    /// Writes the image as a binary PPM (P6) file.
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.rgb_bytes())
+    }
+
+    /// Writes the image as an 8-bit RGB PNG file. The pixel data is stored without compression,
+    /// which keeps the encoder small and is fine for puzzle-sized images.
+    pub fn write_png(&self, mut out: impl Write) -> io::Result<()> {
+        if self.width == 0 || self.height == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "a PNG must be at least one pixel wide and high",
+            ));
+        }
+        let mut header = Vec::with_capacity(13);
+        header.extend((self.width as u32).to_be_bytes());
+        header.extend((self.height as u32).to_be_bytes());
+        // 8 bits per channel, RGB, deflate, no filtering, no interlacing.
+        header.extend([8, 2, 0, 0, 0]);
+
+        // Each row starts with its filter type, which is always none.
+        let mut scanlines = Vec::with_capacity((self.width * 3 + 1) * self.height);
+        for row in self.rgb_bytes().chunks(self.width * 3) {
+            scanlines.push(0);
+            scanlines.extend(row);
+        }
+
+        out.write_all(b"\x89PNG\r\n\x1a\n")?;
+        write_chunk(&mut out, b"IHDR", &header)?;
+        write_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines))?;
+        write_chunk(&mut out, b"IEND", &[])
+    }
+
+    /// Saves the image as PNG or PPM, chosen by the extension of `path`.
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let extension = path.extension().and_then(|e| e.to_str());
+        let png = match extension.map(str::to_ascii_lowercase).as_deref() {
+            Some("png") => true,
+            Some("ppm") => false,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("{} is not a .png or .ppm file", path.display()),
+                ))
+            }
+        };
+        let mut out = BufWriter::new(File::create(path)?);
+        if png {
+            self.write_png(&mut out)?;
+        } else {
+            self.write_ppm(&mut out)?;
+        }
+        out.flush()
+    }
+
+    fn rgb_bytes(&self) -> Vec<u8> {
+        self.pixels
+            .iter()
+            .flat_map(|&Rgb(r, g, b)| [r, g, b])
+            .collect()
+    }
+}
+
+fn write_chunk(out: &mut impl Write, kind: &[u8; 4], data: &[u8]) -> io::Result<()> {
+    out.write_all(&(data.len() as u32).to_be_bytes())?;
+    out.write_all(kind)?;
+    out.write_all(data)?;
+    let crc = crc32(kind.iter().chain(data));
+    out.write_all(&crc.to_be_bytes())
+}
+
+/// Wraps `data` in a zlib stream of uncompressed deflate blocks.
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        stream.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let last = blocks.peek().is_none();
+        let len = block.len() as u16;
+        stream.push(u8::from(last));
+        stream.extend(len.to_le_bytes());
+        stream.extend((!len).to_le_bytes());
+        stream.extend(block);
+    }
+    stream.extend(adler32(data).to_be_bytes());
+    stream
+}
+
+const CRC_TABLE: [u32; 256] = {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = if c & 1 == 1 {
+                0xedb8_8320 ^ (c >> 1)
+            } else {
+                c >> 1
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+};
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    !bytes.into_iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ byte as u32) & 0xff) as usize] ^ (crc >> 8)
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+/// A sequence of images to be shown one after another.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Animation {
+    frames: Vec<Image>,
+}
+
+impl Animation {
+    pub fn new() -> Animation {
+        Animation::default()
+    }
+
+    pub fn push(&mut self, frame: Image) {
+        self.frames.push(frame);
+    }
+
+    pub fn frames(&self) -> &[Image] {
+        &self.frames
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn scale(&self, factor: usize) -> Animation {
+        Animation {
+            frames: self
+                .frames
+                .iter()
+                .map(|frame| frame.scale(factor))
+                .collect(),
+        }
+    }
+
+    /// Draws each frame over the last in the terminal, waiting `delay` between them.
+    pub fn play(&self, mut out: impl Write, delay: Duration) -> io::Result<()> {
+        // Clear the screen once, then move the cursor home before each frame.
+        out.write_all(b"\x1b[2J")?;
+        for frame in &self.frames {
+            write!(out, "\x1b[H{}", frame.to_ansi())?;
+            out.flush()?;
+            thread::sleep(delay);
+        }
+        Ok(())
+    }
+
+    /// Saves the frames into `dir` as `frame-00000.png`, `frame-00001.png` and so on, in the
+    /// format given by `extension`.
+    pub fn save_frames(&self, dir: &Path, extension: &str) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            frame.save(&dir.join(format!("frame-{:05}.{}", i, extension)))?;
+        }
+        Ok(())
+    }
+}
+
+/// What a day draws: a single picture, or one that changes over time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Visual {
+    Image(Image),
+    Animation(Animation),
+}
+
+impl From<Image> for Visual {
+    fn from(image: Image) -> Visual {
+        Visual::Image(image)
+    }
+}
+
+impl From<Animation> for Visual {
+    fn from(animation: Animation) -> Visual {
+        Visual::Animation(animation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::Point;
+
+    fn checkerboard() -> Image {
+        let grid = Grid::parse(&b"#.\n.#\n#.\n"[..], |c| Some(c == '#')).unwrap();
+        Image::from_grid(&grid, |&on| if on { Rgb::WHITE } else { Rgb::RED })
+    }
+
+    #[test]
+    fn test_checksums() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+    }
+
+    #[test]
+    fn test_png() {
+        let mut png = Vec::new();
+        checkerboard().write_png(&mut png).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[8..16], b"\0\0\0\x0dIHDR");
+        assert_eq!(&png[16..29], &[0, 0, 0, 2, 0, 0, 0, 3, 8, 2, 0, 0, 0]);
+        assert!(png.ends_with(&[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82]));
+
+        // A stored block holds the three scanlines of a filter byte and two RGB pixels.
+        let idat = &png[33..];
+        assert_eq!(&idat[..4], &(2 + 5 + 21 + 4u32).to_be_bytes());
+        assert_eq!(&idat[4..8], b"IDAT");
+        assert_eq!(&idat[10..15], &[1, 21, 0, !21, 0xff]);
+        assert_eq!(&idat[15..22], &[0, 255, 255, 255, 220, 50, 47]);
+
+        assert!(Image::new(0, 3, Rgb::BLACK).write_png(Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_ppm_and_ansi() {
+        let image = Image::new(1, 1, Rgb(1, 2, 3));
+        let mut ppm = Vec::new();
+        image.write_ppm(&mut ppm).unwrap();
+        assert_eq!(ppm, b"P6\n1 1\n255\n\x01\x02\x03");
+
+        let ansi = checkerboard().to_ansi();
+        assert_eq!(ansi.lines().count(), 2);
+        assert!(ansi.starts_with("\x1b[38;2;255;255;255m\x1b[48;2;220;50;47m▀"));
+        assert!(ansi.ends_with("\x1b[38;2;220;50;47m\x1b[49m▀\x1b[0m\n"));
+    }
+
+    #[test]
+    fn test_sparse_and_scale() {
+        let mut grid = SparseGrid::new();
+        grid.insert(Point::new(-1, 5), ());
+        grid.insert(Point::new(1, 6), ());
+        let image = Image::from_sparse(&grid, Rgb::BLACK, |_| Rgb::GREEN);
+        assert_eq!((image.width(), image.height()), (3, 2));
+        assert_eq!(image.pixel(0, 0), Some(Rgb::GREEN));
+        assert_eq!(image.pixel(1, 0), Some(Rgb::BLACK));
+        assert_eq!(image.pixel(2, 1), Some(Rgb::GREEN));
+        assert_eq!(image.pixel(3, 1), None);
+
+        let scaled = image.scale(2);
+        assert_eq!((scaled.width(), scaled.height()), (6, 4));
+        assert_eq!(scaled.pixel(1, 1), Some(Rgb::GREEN));
+        assert_eq!(scaled.pixel(2, 1), Some(Rgb::BLACK));
+    }
+}
@@ -0,0 +1,331 @@
+//! Shortest path searches over any state space. Each search takes the states to start from (all
+//! at cost zero, so several starts make a multi-source search) and a successor function from a
+//! state to the states it leads to. Searches with a goal stop at the first goal reached and return
+//! the whole [`Path`]; the `_all` variants explore everything reachable and return the
+//! [`Distances`], from which paths can be rebuilt.
+//!
+//! ```
+//! use advent_of_code_2019::search;
+//!
+//! // Reach 10 from 1 by doubling or adding one.
+//! let path = search::bfs([1], |&n: &u32| [n * 2, n + 1], |&n| n == 10).unwrap();
+//! assert_eq!(path.cost, 4);
+//! assert_eq!(path.states, vec![1, 2, 4, 5, 10]);
+//! ```
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, VecDeque},
+    hash::Hash,
+    ops::Add,
+};
+
+/// A path found by a search, from one of the starts to the goal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    /// Every state along the path, including the start and the goal.
+    pub states: Vec<S>,
+}
+
+/// The lowest cost to every state a search reached, and the state it was reached from.
+#[derive(Debug, Clone)]
+pub struct Distances<S, C> {
+    reached: HashMap<S, (C, Option<S>)>,
+}
+
+impl<S: Clone + Eq + Hash, C: Copy> Distances<S, C> {
+    fn new() -> Distances<S, C> {
+        Distances {
+            reached: HashMap::new(),
+        }
+    }
+
+    pub fn cost(&self, state: &S) -> Option<C> {
+        self.reached.get(state).map(|&(cost, _)| cost)
+    }
+
+    /// The path from a start to `state`, if it was reached.
+    pub fn path_to(&self, state: &S) -> Option<Path<S, C>> {
+        let cost = self.cost(state)?;
+        let mut states = vec![state.clone()];
+        while let Some((_, Some(previous))) = self.reached.get(states.last()?) {
+            states.push(previous.clone());
+        }
+        states.reverse();
+        Some(Path { cost, states })
+    }
+
+    /// Every reached state with its cost, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&S, C)> {
+        self.reached.iter().map(|(state, &(cost, _))| (state, cost))
+    }
+
+    pub fn len(&self) -> usize {
+        self.reached.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reached.is_empty()
+    }
+}
+
+/// Breadth first search where every step costs one.
+pub fn bfs<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let (distances, found) = breadth_first(starts, successors, goal);
+    distances.path_to(&found?)
+}
+
+/// Breadth first search of everything reachable from `starts`.
+pub fn bfs_all<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+) -> Distances<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    breadth_first(starts, successors, |_| false).0
+}
+
+fn breadth_first<S, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut goal: impl FnMut(&S) -> bool,
+) -> (Distances<S, usize>, Option<S>)
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = Distances::new();
+    let mut queue = VecDeque::new();
+    for start in starts {
+        if !distances.reached.contains_key(&start) {
+            distances.reached.insert(start.clone(), (0, None));
+            queue.push_back((start, 0));
+        }
+    }
+    while let Some((state, steps)) = queue.pop_front() {
+        if goal(&state) {
+            return (distances, Some(state));
+        }
+        for next in successors(&state) {
+            if !distances.reached.contains_key(&next) {
+                distances
+                    .reached
+                    .insert(next.clone(), (steps + 1, Some(state.clone())));
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    (distances, None)
+}
+
+/// Dijkstra's search for the cheapest path to a goal.
+pub fn dijkstra<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(starts, successors, |_| C::default(), goal)
+}
+
+/// Dijkstra's search of everything reachable from `starts`.
+pub fn dijkstra_all<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+) -> Distances<S, C>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    best_first(starts, successors, |_| C::default(), |_| false).0
+}
+
+/// A* search for the cheapest path to a goal. `heuristic` estimates the remaining cost from a
+/// state and must never overestimate it, or the path found may not be the cheapest.
+pub fn astar<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    successors: impl FnMut(&S) -> I,
+    heuristic: impl FnMut(&S) -> C,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let (distances, found) = best_first(starts, successors, heuristic, goal);
+    distances.path_to(&found?)
+}
+
+/// A state waiting in the queue, ordered so the [`BinaryHeap`] pops the lowest estimate first
+/// and, between equal estimates, the one furthest along.
+struct Queued<S, C> {
+    estimate: C,
+    cost: C,
+    state: S,
+}
+
+impl<S, C: Ord> Ord for Queued<S, C> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .estimate
+            .cmp(&self.estimate)
+            .then_with(|| self.cost.cmp(&other.cost))
+    }
+}
+
+impl<S, C: Ord> PartialOrd for Queued<S, C> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<S, C: Ord> PartialEq for Queued<S, C> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<S, C: Ord> Eq for Queued<S, C> {}
+
+fn best_first<S, C, I>(
+    starts: impl IntoIterator<Item = S>,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut goal: impl FnMut(&S) -> bool,
+) -> (Distances<S, C>, Option<S>)
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Default + Add<Output = C>,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut distances = Distances::new();
+    let mut queue = BinaryHeap::new();
+    for start in starts {
+        if !distances.reached.contains_key(&start) {
+            distances
+                .reached
+                .insert(start.clone(), (C::default(), None));
+            queue.push(Queued {
+                estimate: heuristic(&start),
+                cost: C::default(),
+                state: start,
+            });
+        }
+    }
+    while let Some(Queued { cost, state, .. }) = queue.pop() {
+        // Skip states that were queued again at a lower cost after this entry.
+        if distances.cost(&state).is_some_and(|best| best < cost) {
+            continue;
+        }
+        if goal(&state) {
+            return (distances, Some(state));
+        }
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            if distances.cost(&next).is_none_or(|best| next_cost < best) {
+                distances
+                    .reached
+                    .insert(next.clone(), (next_cost, Some(state.clone())));
+                queue.push(Queued {
+                    estimate: next_cost + heuristic(&next),
+                    cost: next_cost,
+                    state: next,
+                });
+            }
+        }
+    }
+    (distances, None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grid::{Grid, Point};
+
+    const MAZE: &str = "#########
+#S..#...#
+#.#.#.#.#
+#.#...#E#
+#########";
+
+    fn maze() -> Grid<char> {
+        Grid::parse(MAZE.as_bytes(), Some).unwrap()
+    }
+
+    #[test]
+    fn test_bfs() {
+        let maze = maze();
+        let start = maze.position(|&c| c == 'S').unwrap();
+        let end = maze.position(|&c| c == 'E').unwrap();
+        let open = |p: &Point| {
+            let p = *p;
+            let maze = &maze;
+            maze.neighbours4(p).filter(move |&n| maze[n] != '#')
+        };
+        let path = bfs([start], open, |&p| p == end).unwrap();
+        assert_eq!(path.cost, 12);
+        assert_eq!(path.states.len(), 13);
+        assert_eq!(path.states.first(), Some(&start));
+        assert_eq!(path.states.last(), Some(&end));
+        assert!(path.states.windows(2).all(|w| w[0].manhattan(w[1]) == 1));
+
+        let distances = bfs_all([start, end], open);
+        assert_eq!(distances.cost(&Point::new(5, 1)), Some(4));
+        assert_eq!(distances.iter().map(|(_, cost)| cost).max(), Some(6));
+        assert_eq!(bfs([start], open, |&p| p == Point::ORIGIN), None);
+    }
+
+    #[test]
+    fn test_weighted() {
+        // Going through B is fewer steps but costs more than going through C and D.
+        let successors = |s: &char| -> Vec<(char, u32)> {
+            match s {
+                'A' => vec![('B', 5), ('C', 1)],
+                'B' => vec![('E', 5)],
+                'C' => vec![('D', 1)],
+                'D' => vec![('E', 1)],
+                _ => vec![],
+            }
+        };
+        let path = dijkstra(['A'], successors, |&s| s == 'E').unwrap();
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.states, vec!['A', 'C', 'D', 'E']);
+        let distances = dijkstra_all(['A'], successors);
+        assert_eq!(distances.cost(&'B'), Some(5));
+        assert_eq!(distances.path_to(&'E'), Some(path));
+        assert_eq!(distances.path_to(&'F'), None);
+    }
+
+    #[test]
+    fn test_astar() {
+        let maze = maze();
+        let start = maze.position(|&c| c == 'S').unwrap();
+        let end = maze.position(|&c| c == 'E').unwrap();
+        let open = |p: &Point| {
+            let p = *p;
+            let maze = &maze;
+            maze.neighbours4(p)
+                .filter(move |&n| maze[n] != '#')
+                .map(|n| (n, 1))
+        };
+        let path = astar([start], open, |p| p.manhattan(end), |&p| p == end).unwrap();
+        assert_eq!(Some(path), dijkstra([start], open, |&p| p == end));
+    }
+}
@@ -0,0 +1,224 @@
+//! The registry of every day's solutions, so the runner and the tests can enumerate them.
+//!
+//! ```
+//! use advent_of_code_2019::solution::{self, Answer};
+//!
+//! let solution = solution::get(1, 1).unwrap();
+//! assert_eq!((solution.solve)(&mut &b"12\n14\n"[..])?, Answer::Number(4));
+//! # Ok::<(), solution::SolveError>(())
+//! ```
+
+use std::{
+    error::Error,
+    fmt::Display,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    intcode::{network::NodeError, VmError},
+    parse::ParseError,
+    render::Visual,
+    *,
+};
+
+/// A puzzle answer: a number, or text such as the letters drawn by days 8 and 11.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Answer {
+    Number(usize),
+    Text(String),
+}
+
+impl Display for Answer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Answer::Number(n) => write!(f, "{}", n),
+            Answer::Text(text) => write!(f, "{}", text),
+        }
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(n: usize) -> Answer {
+        Answer::Number(n)
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Answer {
+        Answer::Text(text)
+    }
+}
+
+/// Why a star could not produce an answer.
+#[derive(Debug)]
+pub enum SolveError {
+    Io(io::Error),
+    Parse(ParseError),
+    /// The IntCode program faulted.
+    Vm(VmError),
+    /// One of several networked IntCode programs faulted.
+    Network(NodeError),
+    /// The input was read but has no answer, such as a maze with no route to the goal.
+    NoSolution(String),
+}
+
+impl SolveError {
+    pub fn no_solution(message: impl Into<String>) -> SolveError {
+        SolveError::NoSolution(message.into())
+    }
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::Io(e) => write!(f, "reading input: {}", e),
+            SolveError::Parse(e) => write!(f, "parsing input: {}", e),
+            SolveError::Vm(e) => write!(f, "running IntCode: {}", e),
+            SolveError::Network(e) => write!(f, "running IntCode: {}", e),
+            SolveError::NoSolution(message) => write!(f, "no solution: {}", message),
+        }
+    }
+}
+
+impl Error for SolveError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SolveError::Io(e) => Some(e),
+            SolveError::Parse(e) => Some(e),
+            SolveError::Vm(e) => Some(e),
+            SolveError::Network(e) => Some(e),
+            SolveError::NoSolution(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for SolveError {
+    fn from(e: io::Error) -> SolveError {
+        SolveError::Io(e)
+    }
+}
+
+impl From<ParseError> for SolveError {
+    fn from(e: ParseError) -> SolveError {
+        match e.into_io() {
+            Ok(e) => SolveError::Io(e),
+            Err(e) => SolveError::Parse(e),
+        }
+    }
+}
+
+impl From<VmError> for SolveError {
+    fn from(e: VmError) -> SolveError {
+        SolveError::Vm(e)
+    }
+}
+
+impl From<NodeError> for SolveError {
+    fn from(e: NodeError) -> SolveError {
+        SolveError::Network(e)
+    }
+}
+
+/// One star: reads the puzzle input and returns the answer.
+pub type Solve = fn(&mut dyn BufRead) -> Result<Answer, SolveError>;
+
+/// A registered solution for one part of one day.
+#[derive(Debug, Clone, Copy)]
+pub struct Solution {
+    pub day: usize,
+    pub part: usize,
+    pub solve: Solve,
+}
+
+impl Solution {
+    /// Where the puzzle input for this solution is kept.
+    pub fn input_path(&self) -> PathBuf {
+        input_path(self.day)
+    }
+}
+
+/// Where the puzzle input for `day` is kept.
+pub fn input_path(day: usize) -> PathBuf {
+    input_in(Path::new("data"), day)
+}
+
+/// The puzzle input for `day` in a directory laid out like `data`.
+pub fn input_in(dir: &Path, day: usize) -> PathBuf {
+    dir.join(format!("day{:02}.txt", day))
+}
+
+macro_rules! registry {
+    ($($day:literal => $module:ident),* $(,)?) => {
+        /// Every solution, ordered by day then part.
+        pub static SOLUTIONS: &[Solution] = &[
+            $(
+                Solution {
+                    day: $day,
+                    part: 1,
+                    solve: |input| $module::star_one(input).map(Answer::from),
+                },
+                Solution {
+                    day: $day,
+                    part: 2,
+                    solve: |input| $module::star_two(input).map(Answer::from),
+                },
+            )*
+        ];
+    };
+}
+
+registry! {
+    1 => day01, 2 => day02, 3 => day03, 4 => day04, 5 => day05,
+    6 => day06, 7 => day07, 8 => day08, 9 => day09, 10 => day10,
+    11 => day11, 12 => day12, 13 => day13, 14 => day14, 15 => day15,
+    16 => day16, 17 => day17, 18 => day18, 19 => day19, 20 => day20,
+    21 => day21, 22 => day22, 23 => day23, 24 => day24, 25 => day25,
+}
+
+/// The solution for one part of a day.
+pub fn get(day: usize, part: usize) -> Option<&'static Solution> {
+    SOLUTIONS
+        .iter()
+        .find(|solution| solution.day == day && solution.part == part)
+}
+
+/// Both parts of a day, in order. Empty if there is no such day.
+pub fn day(day: usize) -> impl Iterator<Item = &'static Solution> {
+    SOLUTIONS.iter().filter(move |solution| solution.day == day)
+}
+
+/// Draws a picture of a day's puzzle from its input.
+pub type Draw = fn(&mut dyn BufRead) -> Result<Visual, SolveError>;
+
+/// A day whose puzzle can be drawn, as a still image or an animation.
+#[derive(Debug, Clone, Copy)]
+pub struct Drawing {
+    pub day: usize,
+    pub draw: Draw,
+}
+
+/// Every day that can be drawn, ordered by day.
+pub static DRAWINGS: &[Drawing] = &[
+    Drawing {
+        day: 8,
+        draw: |input| day08::render(input).map(Visual::from),
+    },
+    Drawing {
+        day: 11,
+        draw: |input| day11::render(input).map(Visual::from),
+    },
+    Drawing {
+        day: 13,
+        draw: |input| day13::animate(input).map(Visual::from),
+    },
+    Drawing {
+        day: 24,
+        draw: |input| day24::animate(input).map(Visual::from),
+    },
+];
+
+/// The drawing for a day, if it has one.
+pub fn drawing(day: usize) -> Option<&'static Drawing> {
+    DRAWINGS.iter().find(|drawing| drawing.day == day)
+}